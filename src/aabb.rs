@@ -0,0 +1,67 @@
+use crate::Vector3;
+use crate::ray::Ray;
+
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Clone)]
+pub struct AABB {
+    pub min: Vector3,
+    pub max: Vector3,
+}
+
+impl AABB {
+    pub const fn empty() -> AABB {
+        AABB {
+            min: Vector3 { x: f64::INFINITY, y: f64::INFINITY, z: f64::INFINITY },
+            max: Vector3 { x: f64::NEG_INFINITY, y: f64::NEG_INFINITY, z: f64::NEG_INFINITY },
+        }
+    }
+
+    pub fn surrounding(&self, other: &AABB) -> AABB {
+        AABB {
+            min: self.min.min(&other.min),
+            max: self.max.max(&other.max),
+        }
+    }
+
+    pub fn surrounding_point(&self, point: &Vector3) -> AABB {
+        AABB {
+            min: self.min.min(point),
+            max: self.max.max(point),
+        }
+    }
+
+    pub fn centroid(&self) -> Vector3 {
+        (&self.min + &self.max) * 0.5
+    }
+
+    pub fn surface_area(&self) -> f64 {
+        let extent: Vector3 = &self.max - &self.min;
+        if extent.x < 0.0 || extent.y < 0.0 || extent.z < 0.0 {
+            return 0.0;
+        }
+
+        2.0 * (extent.x * extent.y + extent.y * extent.z + extent.z * extent.x)
+    }
+
+    pub fn ray_hits_aabb(&self, ray: &Ray, inverse_direction: &Vector3, mut t_min: f64, mut t_max: f64) -> bool {
+        for axis in 0..3 {
+            let origin: f64 = ray.origin.axis(axis);
+            let inverse: f64 = inverse_direction.axis(axis);
+
+            let mut t0: f64 = (self.min.axis(axis) - origin) * inverse;
+            let mut t1: f64 = (self.max.axis(axis) - origin) * inverse;
+            if inverse < 0.0 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+
+            t_min = if t0 > t_min { t0 } else { t_min };
+            t_max = if t1 < t_max { t1 } else { t_max };
+
+            if t_max < t_min {
+                return false;
+            }
+        }
+
+        true
+    }
+}
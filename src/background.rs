@@ -0,0 +1,185 @@
+use std::f64::consts::PI;
+use std::path::Path;
+use image::DynamicImage;
+use crate::Vector3;
+use crate::color_util::{decode_pixels, is_linear_format, luminance};
+use crate::distribution::Distribution2D;
+
+pub enum Background {
+    SOLID(Vector3),
+    GRADIENT(Vector3, Vector3),
+    IMAGE(EnvironmentMap),
+}
+
+// Equirectangular (latitude-longitude) environment image with linear radiance values. Directions are importance
+// sampled proportional to the luminance of the pixels, so that small and very bright regions like the sun are found
+// by light sampling instead of randomly bouncing into them.
+pub struct EnvironmentMap {
+    width: usize,
+    height: usize,
+    pixels: Vec<Vector3>,
+    rotation: f64,
+    intensity: f64,
+    distribution: Distribution2D,
+}
+
+impl Background {
+    pub const fn sky() -> Background {
+        Background::GRADIENT(Vector3 { x: 1.0, y: 1.0, z: 1.0 }, Vector3 { x: 0.5, y: 0.7, z: 1.0 })
+    }
+
+    // Parses a background given on the command line: `sky`, `black`, `solid:R,G,B`, `gradient:R,G,B:R,G,B`
+    // (horizon and zenith color) or `image:PATH` (PNG, JPEG, Radiance HDR, OpenEXR, ...). Images are loaded without
+    // rotation and with an intensity of 1.
+    pub fn parse(specification: &str) -> Result<Background, String> {
+        let (kind, arguments) = specification.split_once(':').unwrap_or((specification, ""));
+
+        match (kind, arguments) {
+            ("sky", "") => Ok(Background::sky()),
+            ("black", "") => Ok(Background::SOLID(Vector3::zero())),
+            ("solid", color) => Ok(Background::SOLID(parse_color(color)?)),
+            ("gradient", colors) => match colors.split_once(':') {
+                Some((bottom, top)) => Ok(Background::GRADIENT(parse_color(bottom)?, parse_color(top)?)),
+                None => Err(format!("Invalid gradient background '{}', expected gradient:R,G,B:R,G,B", specification)),
+            },
+            ("image", path) if !path.is_empty() => Ok(Background::IMAGE(EnvironmentMap::load(Path::new(path), 0.0, 1.0)?)),
+            _ => Err(format!("Invalid background '{}', expected sky, black, solid:R,G,B, gradient:R,G,B:R,G,B or image:PATH", specification)),
+        }
+    }
+
+    pub fn get_color(&self, direction: &Vector3) -> Vector3 {
+        match self {
+            Background::SOLID(color) => color.clone(),
+            Background::GRADIENT(bottom, top) => {
+                let t: f64 = 0.5 * (direction.normalized().y + 1.0);
+                bottom * (1.0 - t) + top * t
+            }
+            Background::IMAGE(environment_map) => environment_map.get_color(direction),
+        }
+    }
+
+    // Samples a direction towards the background, if it benefits from importance sampling
+    pub fn sample(&self, random: &mut fastrand::Rng) -> Option<(Vector3, f64)> {
+        match self {
+            Background::IMAGE(environment_map) => Some(environment_map.sample(random)),
+            _ => None,
+        }
+    }
+
+    // Probability density of `sample` returning the given direction, or 0 if the background is not sampled
+    pub fn pdf(&self, direction: &Vector3) -> f64 {
+        match self {
+            Background::IMAGE(environment_map) => environment_map.pdf(direction),
+            _ => 0.0,
+        }
+    }
+}
+
+impl EnvironmentMap {
+    // Loads an equirectangular image, rotated around the vertical axis by `rotation` degrees and scaled by `intensity`
+    pub fn load(path: &Path, rotation: f64, intensity: f64) -> Result<EnvironmentMap, String> {
+        let image: DynamicImage = image::open(path)
+            .map_err(|error| format!("Failed to load environment image '{}': {}", path.display(), error))?;
+
+        let pixels: Vec<Vector3> = decode_pixels(&image, !is_linear_format(&image));
+        let width: usize = image.width() as usize;
+        let height: usize = image.height() as usize;
+
+        // weight by sin(theta), since rows near the poles cover a smaller solid angle
+        let weights: Vec<f64> = pixels.iter().enumerate()
+            .map(|(i, pixel)| luminance(pixel).max(0.0) * (PI * ((i / width) as f64 + 0.5) / height as f64).sin())
+            .collect();
+
+        Ok(EnvironmentMap {
+            width,
+            height,
+            pixels,
+            rotation: rotation.to_radians(),
+            intensity,
+            distribution: Distribution2D::new(&weights, width, height),
+        })
+    }
+
+    // Rotation around the vertical axis in degrees
+    pub fn set_rotation(&mut self, rotation: f64) {
+        self.rotation = rotation.to_radians();
+    }
+
+    // Scaling the radiance does not change the relative luminance the directions are sampled by
+    pub fn set_intensity(&mut self, intensity: f64) {
+        self.intensity = intensity;
+    }
+
+    pub fn get_color(&self, direction: &Vector3) -> Vector3 {
+        let (u, v) = self.direction_to_uv(direction);
+        self.lookup(u, v) * self.intensity
+    }
+
+    pub fn sample(&self, random: &mut fastrand::Rng) -> (Vector3, f64) {
+        let (u, v, uv_pdf) = self.distribution.sample(random.f64(), random.f64());
+        let sin_theta: f64 = (v * PI).sin();
+
+        let pdf: f64 = if sin_theta > 0.0 { uv_pdf / (2.0 * PI * PI * sin_theta) } else { 0.0 };
+        (self.uv_to_direction(u, v), pdf)
+    }
+
+    pub fn pdf(&self, direction: &Vector3) -> f64 {
+        let (u, v) = self.direction_to_uv(direction);
+        let sin_theta: f64 = (v * PI).sin();
+
+        if sin_theta > 0.0 { self.distribution.pdf(u, v) / (2.0 * PI * PI * sin_theta) } else { 0.0 }
+    }
+
+    fn direction_to_uv(&self, direction: &Vector3) -> (f64, f64) {
+        let direction: Vector3 = rotate_around_y(&direction.normalized(), -self.rotation);
+        let u: f64 = 0.5 + direction.x.atan2(-direction.z) / (2.0 * PI);
+        let v: f64 = direction.y.clamp(-1.0, 1.0).acos() / PI;
+        (u.clamp(0.0, 1.0), v)
+    }
+
+    fn uv_to_direction(&self, u: f64, v: f64) -> Vector3 {
+        let (sin_theta, cos_theta) = (v * PI).sin_cos();
+        let (sin_phi, cos_phi) = ((u - 0.5) * 2.0 * PI).sin_cos();
+
+        let direction: Vector3 = Vector3 { x: sin_theta * sin_phi, y: cos_theta, z: -sin_theta * cos_phi };
+        rotate_around_y(&direction, self.rotation)
+    }
+
+    fn lookup(&self, u: f64, v: f64) -> Vector3 {
+        // bilinear interpolation, wrapping around horizontally
+        let x: f64 = u * self.width as f64 - 0.5;
+        let y: f64 = (v * self.height as f64 - 0.5).clamp(0.0, (self.height - 1) as f64);
+        let x0: f64 = x.floor();
+        let y0: f64 = y.floor();
+        let fx: f64 = x - x0;
+        let fy: f64 = y - y0;
+
+        let column = |offset: f64| -> usize { (x0 + offset).rem_euclid(self.width as f64) as usize % self.width };
+        let row = |offset: f64| -> usize { ((y0 + offset) as usize).min(self.height - 1) };
+
+        let top: Vector3 = self.pixel(column(0.0), row(0.0)) * (1.0 - fx) + self.pixel(column(1.0), row(0.0)) * fx;
+        let bottom: Vector3 = self.pixel(column(0.0), row(1.0)) * (1.0 - fx) + self.pixel(column(1.0), row(1.0)) * fx;
+        top * (1.0 - fy) + bottom * fy
+    }
+
+    fn pixel(&self, x: usize, y: usize) -> &Vector3 {
+        &self.pixels[y * self.width + x]
+    }
+}
+
+fn rotate_around_y(vector: &Vector3, angle: f64) -> Vector3 {
+    let (sin, cos) = angle.sin_cos();
+    Vector3 { x: vector.x * cos + vector.z * sin, y: vector.y, z: -vector.x * sin + vector.z * cos }
+}
+
+fn parse_color(value: &str) -> Result<Vector3, String> {
+    let components: Vec<f64> = value.split(',')
+        .map(|component| component.trim().parse::<f64>())
+        .collect::<Result<_, _>>()
+        .map_err(|_| format!("Invalid color '{}', expected R,G,B", value))?;
+
+    match components[..] {
+        [r, g, b] => Ok(Vector3 { x: r, y: g, z: b }),
+        _ => Err(format!("Invalid color '{}', expected R,G,B", value)),
+    }
+}
@@ -0,0 +1,233 @@
+use crate::Vector3;
+use crate::hit_record::HitRecord;
+use crate::ray::Ray;
+use crate::aabb::AABB;
+use crate::hittable::Hittable;
+
+const BIN_COUNT: usize = 16;
+const MAX_LEAF_SIZE: usize = 4;
+const MAX_DEPTH: usize = 64;
+const TRAVERSAL_COST: f64 = 0.125;
+
+#[allow(clippy::upper_case_acronyms)]
+struct BVHNode {
+    bounding_box: AABB,
+    first_primitive: usize,
+    primitive_count: usize,
+    second_child: usize,
+    split_axis: usize,
+}
+
+#[allow(clippy::upper_case_acronyms)]
+pub struct BVH {
+    nodes: Vec<BVHNode>,
+    primitive_indices: Vec<usize>,
+}
+
+struct Bin {
+    bounding_box: AABB,
+    primitive_count: usize,
+}
+
+impl BVH {
+    pub fn build(bounding_boxes: &[AABB]) -> BVH {
+        let centroids: Vec<Vector3> = bounding_boxes.iter().map(|bounding_box| bounding_box.centroid()).collect();
+
+        let mut bvh = BVH {
+            nodes: Vec::with_capacity(bounding_boxes.len() * 2),
+            primitive_indices: (0..bounding_boxes.len()).collect(),
+        };
+
+        // an empty scene has no root node, every node with a primitive count of zero is an interior node with two children
+        if bounding_boxes.is_empty() {
+            return bvh;
+        }
+
+        bvh.build_node(bounding_boxes, &centroids, 0, bounding_boxes.len(), 0);
+        bvh
+    }
+
+    fn build_node(&mut self, bounding_boxes: &[AABB], centroids: &[Vector3], first: usize, count: usize, depth: usize) -> usize {
+        let mut bounding_box: AABB = AABB::empty();
+        let mut centroid_bounds: AABB = AABB::empty();
+        for &index in &self.primitive_indices[first..first + count] {
+            bounding_box = bounding_box.surrounding(&bounding_boxes[index]);
+            centroid_bounds = centroid_bounds.surrounding_point(&centroids[index]);
+        }
+
+        let parent_area: f64 = bounding_box.surface_area();
+        let node_index: usize = self.nodes.len();
+        self.nodes.push(BVHNode {
+            bounding_box,
+            first_primitive: first,
+            primitive_count: count,
+            second_child: 0,
+            split_axis: 0,
+        });
+
+        if count <= 1 || depth >= MAX_DEPTH {
+            return node_index;
+        }
+
+        let (split_axis, left_count) = match self.find_sah_split(bounding_boxes, centroids, &centroid_bounds, parent_area, first, count) {
+            Some(split) => split,
+            None if count <= MAX_LEAF_SIZE => return node_index,
+            None => self.split_at_median(centroids, &centroid_bounds, first, count),
+        };
+
+        self.build_node(bounding_boxes, centroids, first, left_count, depth + 1);
+        let second_child: usize = self.build_node(bounding_boxes, centroids, first + left_count, count - left_count, depth + 1);
+
+        let node: &mut BVHNode = &mut self.nodes[node_index];
+        node.primitive_count = 0;
+        node.second_child = second_child;
+        node.split_axis = split_axis;
+
+        node_index
+    }
+
+    // Binned surface area heuristic. Returns the split axis and the size of the left partition, or `None` if keeping
+    // the primitives in a leaf is estimated to be cheaper than splitting them.
+    fn find_sah_split(&mut self, bounding_boxes: &[AABB], centroids: &[Vector3], centroid_bounds: &AABB, parent_area: f64, first: usize, count: usize) -> Option<(usize, usize)> {
+        let leaf_cost: f64 = count as f64;
+
+        let mut best_cost: f64 = f64::INFINITY;
+        let mut best_axis: usize = 0;
+        let mut best_split: usize = 0;
+
+        for axis in 0..3 {
+            let axis_min: f64 = centroid_bounds.min.axis(axis);
+            let axis_extent: f64 = centroid_bounds.max.axis(axis) - axis_min;
+            if axis_extent <= 0.0 {
+                continue;
+            }
+
+            let mut bins: Vec<Bin> = (0..BIN_COUNT).map(|_| Bin { bounding_box: AABB::empty(), primitive_count: 0 }).collect();
+            for &index in &self.primitive_indices[first..first + count] {
+                let bin: &mut Bin = &mut bins[BVH::bin_index(centroids[index].axis(axis), axis_min, axis_extent)];
+                bin.bounding_box = bin.bounding_box.surrounding(&bounding_boxes[index]);
+                bin.primitive_count += 1;
+            }
+
+            let mut right_areas: [f64; BIN_COUNT] = [0.0; BIN_COUNT];
+            let mut right_counts: [usize; BIN_COUNT] = [0; BIN_COUNT];
+            let mut right_box: AABB = AABB::empty();
+            let mut right_count: usize = 0;
+            for bin in (1..BIN_COUNT).rev() {
+                right_box = right_box.surrounding(&bins[bin].bounding_box);
+                right_count += bins[bin].primitive_count;
+                right_areas[bin] = right_box.surface_area();
+                right_counts[bin] = right_count;
+            }
+
+            let mut left_box: AABB = AABB::empty();
+            let mut left_count: usize = 0;
+            for split in 1..BIN_COUNT {
+                left_box = left_box.surrounding(&bins[split - 1].bounding_box);
+                left_count += bins[split - 1].primitive_count;
+                if left_count == 0 || right_counts[split] == 0 {
+                    continue;
+                }
+
+                let cost: f64 = TRAVERSAL_COST
+                    + (left_box.surface_area() * left_count as f64 + right_areas[split] * right_counts[split] as f64) / parent_area;
+
+                if cost < best_cost {
+                    best_cost = cost;
+                    best_axis = axis;
+                    best_split = split;
+                }
+            }
+        }
+
+        if best_cost == f64::INFINITY || (count <= MAX_LEAF_SIZE && leaf_cost <= best_cost) {
+            return None;
+        }
+
+        let axis_min: f64 = centroid_bounds.min.axis(best_axis);
+        let axis_extent: f64 = centroid_bounds.max.axis(best_axis) - axis_min;
+        let left_count: usize = self.partition(first, count, |index| {
+            BVH::bin_index(centroids[index].axis(best_axis), axis_min, axis_extent) < best_split
+        });
+
+        Some((best_axis, left_count))
+    }
+
+    fn split_at_median(&mut self, centroids: &[Vector3], centroid_bounds: &AABB, first: usize, count: usize) -> (usize, usize) {
+        let extent: Vector3 = &centroid_bounds.max - &centroid_bounds.min;
+        let axis: usize = if extent.x >= extent.y && extent.x >= extent.z { 0 } else if extent.y >= extent.z { 1 } else { 2 };
+
+        self.primitive_indices[first..first + count]
+            .sort_by(|a, b| centroids[*a].axis(axis).total_cmp(&centroids[*b].axis(axis)));
+
+        (axis, count / 2)
+    }
+
+    fn partition<F: Fn(usize) -> bool>(&mut self, first: usize, count: usize, is_left: F) -> usize {
+        let indices: &mut [usize] = &mut self.primitive_indices[first..first + count];
+        let mut left_count: usize = 0;
+
+        for i in 0..indices.len() {
+            if is_left(indices[i]) {
+                indices.swap(i, left_count);
+                left_count += 1;
+            }
+        }
+
+        left_count
+    }
+
+    fn bin_index(value: f64, axis_min: f64, axis_extent: f64) -> usize {
+        let bin: usize = ((value - axis_min) / axis_extent * BIN_COUNT as f64) as usize;
+        bin.min(BIN_COUNT - 1)
+    }
+
+    pub fn ray_hit<'a>(&self, objects: &'a [Box<dyn Hittable>], ray: &Ray, t_min: f64, t_max: f64) -> HitRecord<'a> {
+        let mut current_record: HitRecord<'a> = HitRecord::no_hit();
+        current_record.t = t_max;
+
+        if self.nodes.is_empty() {
+            return current_record;
+        }
+
+        let inverse_direction: Vector3 = Vector3 { x: 1.0 / ray.direction.x, y: 1.0 / ray.direction.y, z: 1.0 / ray.direction.z };
+        let direction_is_negative: [bool; 3] = [inverse_direction.x < 0.0, inverse_direction.y < 0.0, inverse_direction.z < 0.0];
+
+        let mut stack: [usize; MAX_DEPTH + 1] = [0; MAX_DEPTH + 1];
+        let mut stack_size: usize = 1;
+
+        while stack_size > 0 {
+            stack_size -= 1;
+            let node: &BVHNode = &self.nodes[stack[stack_size]];
+
+            if !node.bounding_box.ray_hits_aabb(ray, &inverse_direction, t_min, current_record.t) {
+                continue;
+            }
+
+            if node.primitive_count > 0 {
+                for &index in &self.primitive_indices[node.first_primitive..node.first_primitive + node.primitive_count] {
+                    let mut record: HitRecord = objects[index].ray_hit(ray, t_min, current_record.t);
+                    if record.hit {
+                        record.object = index;
+                        current_record = record;
+                    }
+                }
+
+                continue;
+            }
+
+            // visit the child closer to the ray origin first, so that farther subtrees can be culled by the closer hit
+            let first_child: usize = stack[stack_size] + 1;
+            if direction_is_negative[node.split_axis] {
+                stack[stack_size] = first_child;
+                stack[stack_size + 1] = node.second_child;
+            } else {
+                stack[stack_size] = node.second_child;
+                stack[stack_size + 1] = first_child;
+            }
+            stack_size += 2;
+        }
+
+        current_record
+    }
+}
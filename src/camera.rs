@@ -1,4 +1,5 @@
-use crate::{IMAGE_WIDTH, IMAGE_HEIGHT, Vector3, Ray};
+use crate::Vector3;
+use crate::ray::Ray;
 
 pub struct Camera {
     look_from: Vector3,
@@ -11,11 +12,9 @@ pub struct Camera {
 }
 
 impl Camera {
-    pub fn new(look_from: Vector3, look_at: Vector3, fov: f64, aperture: f64, focus_distance: f64) -> Camera {
-        const ASPECT_RATIO: f64 = (IMAGE_WIDTH as f64) / (IMAGE_HEIGHT as f64);
-
+    pub fn new(look_from: Vector3, look_at: Vector3, fov: f64, aperture: f64, focus_distance: f64, aspect_ratio: f64) -> Camera {
         let viewport_height: f64 = (fov.to_radians() / 2.0).tan() * 2.0;
-        let viewport_width: f64 = viewport_height * ASPECT_RATIO;
+        let viewport_width: f64 = viewport_height * aspect_ratio;
 
         let forward: Vector3 = (&look_at - &look_from).normalized();
         let right: Vector3 = Vector3::up().cross(&forward).normalized();
@@ -37,9 +36,9 @@ impl Camera {
         }
     }
 
-    pub fn get_ray(&self, u: f64, v: f64) -> Ray {
-        let random: Vector3 = Vector3::random_unit_vector() * (self.aperture * 0.5);
-        let offset: Vector3 = &self.up_vector * random.y + &self.right_vector * random.x;
+    pub fn get_ray(&self, u: f64, v: f64, random: &mut fastrand::Rng) -> Ray {
+        let lens_offset: Vector3 = Vector3::random_unit_vector(random) * (self.aperture * 0.5);
+        let offset: Vector3 = &self.up_vector * lens_offset.y + &self.right_vector * lens_offset.x;
 
         let origin: Vector3 = &self.look_from + &offset;
         let target: Vector3 = &self.upper_left_corner + &(&self.horizontal_direction * u - &self.vertical_direction * v);
@@ -0,0 +1,77 @@
+use std::fs;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use crate::framebuffer::AccumulationBuffer;
+
+const MAGIC: &[u8; 8] = b"RTCKPT03";
+
+// Everything that determines the content of the rendered image besides the samples themselves, so that a resumed
+// render continues with exactly the same image
+#[derive(Serialize, Deserialize)]
+pub struct CheckpointSettings {
+    pub seed: u64,
+    pub width: u32,
+    pub height: u32,
+    pub max_depth: u32,
+    pub russian_roulette_depth: u32,
+    // canonical path of the scene file and the hash of its content, a checkpoint is only resumed with the same scene
+    pub scene: Option<String>,
+    pub scene_hash: Option<u64>,
+    pub background: Option<String>,
+    pub environment_rotation: Option<f64>,
+    pub environment_intensity: Option<f64>,
+}
+
+// Writes the settings and the accumulated samples of a render. The checkpoint is written to a temporary file first,
+// so that an interrupted write does not destroy the previous checkpoint.
+pub fn save(path: &Path, settings: &CheckpointSettings, accumulation_buffer: &AccumulationBuffer) -> Result<(), String> {
+    let file_name: String = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+    let temporary_path: PathBuf = path.with_file_name(format!(".{}.tmp", file_name));
+
+    let write = || -> Result<(), Box<dyn std::error::Error>> {
+        let mut writer = BufWriter::new(File::create(&temporary_path)?);
+        writer.write_all(MAGIC)?;
+        bincode::serialize_into(&mut writer, &(settings, accumulation_buffer))?;
+        writer.flush()?;
+        fs::rename(&temporary_path, path)?;
+        Ok(())
+    };
+
+    write().map_err(|error| {
+        let _ = fs::remove_file(&temporary_path);
+        format!("Failed to save checkpoint to '{}': {}", path.display(), error)
+    })
+}
+
+pub fn load(path: &Path) -> Result<(CheckpointSettings, AccumulationBuffer), String> {
+    let read = || -> Result<(CheckpointSettings, AccumulationBuffer), Box<dyn std::error::Error>> {
+        let mut reader = BufReader::new(File::open(path)?);
+
+        let mut magic: [u8; 8] = [0; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err("not a checkpoint file".into());
+        }
+
+        let (settings, accumulation_buffer): (CheckpointSettings, AccumulationBuffer) = bincode::deserialize_from(reader)?;
+        let pixel_count: Option<usize> = (settings.width as usize).checked_mul(settings.height as usize);
+        if accumulation_buffer.width != settings.width || accumulation_buffer.height != settings.height
+            || pixel_count != Some(accumulation_buffer.pixels.len()) {
+            return Err("image size does not match the stored pixels".into());
+        }
+
+        Ok((settings, accumulation_buffer))
+    };
+
+    read().map_err(|error| format!("Failed to load checkpoint '{}': {}", path.display(), error))
+}
+
+// 64 bit FNV-1a hash of a file, stored in checkpoints to detect scene files that were changed before resuming
+pub fn hash_file(path: &Path) -> Result<u64, String> {
+    let content: Vec<u8> = fs::read(path).map_err(|error| format!("Failed to read scene file '{}': {}", path.display(), error))?;
+
+    let hash: u64 = content.iter().fold(0xCBF29CE484222325, |hash, byte| (hash ^ *byte as u64).wrapping_mul(0x100000001B3));
+    Ok(hash)
+}
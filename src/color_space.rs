@@ -0,0 +1,71 @@
+use clap::ValueEnum;
+use image::Rgb;
+use crate::Vector3;
+use crate::icc;
+
+#[allow(non_camel_case_types)]
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum ColorSpace {
+    /// Rec. 709 primaries with the sRGB transfer function
+    SRGB,
+    /// Display P3 primaries with the sRGB transfer function
+    DISPLAY_P3,
+    /// Rec. 709 primaries without a transfer function
+    LINEAR_REC709,
+}
+
+impl ColorSpace {
+    // Converts a color from the linear Rec. 709 working space into this color space, without clipping
+    pub fn encode(&self, color: &Vector3) -> Vector3 {
+        match self {
+            ColorSpace::SRGB => map_channels(color, srgb_oetf),
+            ColorSpace::DISPLAY_P3 => map_channels(&rec709_to_display_p3(color), srgb_oetf),
+            ColorSpace::LINEAR_REC709 => color.clone(),
+        }
+    }
+
+    // ICC profile that has to be embedded into output files, so that viewers don't display the values as sRGB
+    pub fn icc_profile(&self) -> Option<Vec<u8>> {
+        match self {
+            ColorSpace::DISPLAY_P3 => Some(icc::display_p3_profile()),
+            ColorSpace::LINEAR_REC709 => Some(icc::linear_rec709_profile()),
+            // untagged images are displayed as sRGB
+            ColorSpace::SRGB => None,
+        }
+    }
+
+    // Encodes and quantizes a color to 8 bits. With a random number generator for `dither`, triangular noise of one
+    // quantization step is added before rounding to break up banding in smooth gradients.
+    pub fn quantize(&self, color: &Vector3, mut dither: Option<&mut fastrand::Rng>) -> Rgb<u8> {
+        let encoded: Vector3 = self.encode(color);
+        let mut quantize_channel = |value: f64| -> u8 {
+            let noise: f64 = match dither.as_deref_mut() {
+                Some(random) => random.f64() - random.f64(),
+                None => 0.0,
+            };
+            (value.clamp(0.0, 1.0) * 255.0 + noise).round().clamp(0.0, 255.0) as u8
+        };
+
+        Rgb([quantize_channel(encoded.x), quantize_channel(encoded.y), quantize_channel(encoded.z)])
+    }
+}
+
+fn srgb_oetf(value: f64) -> f64 {
+    if value <= 0.0031308 { value * 12.92 } else { 1.055 * value.powf(1.0 / 2.4) - 0.055 }
+}
+
+fn rec709_to_display_p3(color: &Vector3) -> Vector3 {
+    Vector3 {
+        x: 0.8224621 * color.x + 0.1775380 * color.y,
+        y: 0.0331941 * color.x + 0.9668058 * color.y,
+        z: 0.0170827 * color.x + 0.0723974 * color.y + 0.9105199 * color.z,
+    }
+}
+
+fn map_channels<F: Fn(f64) -> f64>(color: &Vector3, function: F) -> Vector3 {
+    Vector3 {
+        x: function(color.x.max(0.0)),
+        y: function(color.y.max(0.0)),
+        z: function(color.z.max(0.0)),
+    }
+}
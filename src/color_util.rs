@@ -1,10 +1,36 @@
+use image::DynamicImage;
 use crate::Vector3;
 
-pub fn random_color() -> Vector3 {
-    let h: f64 = (fastrand::f64() * 360.0).floor();
+pub fn random_color(random: &mut fastrand::Rng) -> Vector3 {
+    let h: f64 = (random.f64() * 360.0).floor();
     hsv_to_rgb(h, 0.75, 0.45)
 }
 
+pub fn luminance(color: &Vector3) -> f64 {
+    0.2126 * color.x + 0.7152 * color.y + 0.0722 * color.z
+}
+
+// Floating point formats (HDR, EXR) store linear values, everything else is usually sRGB encoded
+pub fn is_linear_format(image: &DynamicImage) -> bool {
+    matches!(image, DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_))
+}
+
+// Converts the pixels of an image row by row into linear colors, ignoring the alpha channel
+pub fn decode_pixels(image: &DynamicImage, is_srgb: bool) -> Vec<Vector3> {
+    let decode = |value: f32| -> f64 {
+        if is_srgb { srgb_to_linear(value as f64) } else { value as f64 }
+    };
+
+    image.to_rgb32f().pixels()
+        .map(|pixel| Vector3 { x: decode(pixel[0]), y: decode(pixel[1]), z: decode(pixel[2]) })
+        .collect()
+}
+
+pub fn srgb_to_linear(value: f64) -> f64 {
+    if value <= 0.04045 { value / 12.92 } else { ((value + 0.055) / 1.055).powf(2.4) }
+}
+
+#[allow(clippy::manual_range_contains)]
 pub fn hsv_to_rgb(h: f64, s: f64, v: f64) -> Vector3 {
     let c: f64 = s * v;
     let x: f64 = c * (1.0 - ((h / 60.0) % 2.0 - 1.0).abs());
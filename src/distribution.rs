@@ -0,0 +1,84 @@
+// Piecewise constant 1D distribution over [0, 1), sampled by inverting its cumulative distribution function
+pub struct Distribution1D {
+    function: Vec<f64>,
+    cdf: Vec<f64>,
+    integral: f64,
+}
+
+impl Distribution1D {
+    pub fn new(function: Vec<f64>) -> Distribution1D {
+        let count: usize = function.len();
+        let mut cdf: Vec<f64> = Vec::with_capacity(count + 1);
+        cdf.push(0.0);
+
+        for (i, value) in function.iter().enumerate() {
+            cdf.push(cdf[i] + value / count as f64);
+        }
+
+        let integral: f64 = cdf[count];
+        for (i, value) in cdf.iter_mut().enumerate() {
+            *value = if integral > 0.0 { *value / integral } else { i as f64 / count as f64 };
+        }
+
+        Distribution1D { function, cdf, integral }
+    }
+
+    // Returns the sampled position in [0, 1), its probability density and the index of the sampled segment
+    pub fn sample(&self, random: f64) -> (f64, f64, usize) {
+        let index: usize = self.cdf.partition_point(|value| *value <= random).clamp(1, self.function.len()) - 1;
+
+        let segment: f64 = self.cdf[index + 1] - self.cdf[index];
+        let offset: f64 = if segment > 0.0 { (random - self.cdf[index]) / segment } else { 0.0 };
+
+        ((index as f64 + offset) / self.function.len() as f64, self.pdf(index), index)
+    }
+
+    pub fn pdf(&self, index: usize) -> f64 {
+        if self.integral > 0.0 { self.function[index] / self.integral } else { 1.0 }
+    }
+
+    // Probability of `sample` choosing the segment `index`
+    pub fn probability(&self, index: usize) -> f64 {
+        self.pdf(index) / self.function.len() as f64
+    }
+
+    pub fn len(&self) -> usize {
+        self.function.len()
+    }
+}
+
+// Piecewise constant 2D distribution over [0, 1)², stored as a marginal distribution over the rows and one
+// conditional distribution per row
+pub struct Distribution2D {
+    conditionals: Vec<Distribution1D>,
+    marginal: Distribution1D,
+}
+
+impl Distribution2D {
+    pub fn new(function: &[f64], width: usize, height: usize) -> Distribution2D {
+        let conditionals: Vec<Distribution1D> = function.chunks_exact(width)
+            .take(height)
+            .map(|row| Distribution1D::new(row.to_vec()))
+            .collect();
+
+        let marginal: Distribution1D = Distribution1D::new(conditionals.iter().map(|row| row.integral).collect());
+
+        Distribution2D { conditionals, marginal }
+    }
+
+    // Returns the sampled (u, v) position and its probability density
+    pub fn sample(&self, random_u: f64, random_v: f64) -> (f64, f64, f64) {
+        let (v, marginal_pdf, row) = self.marginal.sample(random_v);
+        let (u, conditional_pdf, _) = self.conditionals[row].sample(random_u);
+
+        (u, v, marginal_pdf * conditional_pdf)
+    }
+
+    pub fn pdf(&self, u: f64, v: f64) -> f64 {
+        let row: usize = ((v * self.marginal.len() as f64) as usize).min(self.marginal.len() - 1);
+        let conditional: &Distribution1D = &self.conditionals[row];
+        let column: usize = ((u * conditional.len() as f64) as usize).min(conditional.len() - 1);
+
+        conditional.function[column] / self.marginal.integral.max(f64::MIN_POSITIVE)
+    }
+}
@@ -0,0 +1,148 @@
+use std::ops::Range;
+use serde::{Deserialize, Serialize};
+use crate::Vector3;
+use crate::color_util::{hsv_to_rgb, luminance, srgb_to_linear};
+use crate::tiles::Tile;
+
+const MIN_RELATIVE_ERROR_LUMINANCE: f64 = 0.01;
+
+// Linear radiance of every pixel, stored row by row from the top left corner
+pub struct Framebuffer {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<Vector3>,
+}
+
+impl Framebuffer {
+    pub fn get_pixel(&self, x: u32, y: u32) -> &Vector3 {
+        &self.pixels[(y * self.width + x) as usize]
+    }
+}
+
+// Running mean of the radiance samples of a pixel and the variance of their luminance, updated with Welford's
+// algorithm to stay numerically stable for large sample counts
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PixelStatistics {
+    pub sample_count: u32,
+    pub mean: Vector3,
+    luminance_mean: f64,
+    luminance_m2: f64,
+}
+
+impl PixelStatistics {
+    pub fn new() -> PixelStatistics {
+        PixelStatistics { sample_count: 0, mean: Vector3::zero(), luminance_mean: 0.0, luminance_m2: 0.0 }
+    }
+
+    pub fn add_sample(&mut self, color: &Vector3) {
+        self.sample_count += 1;
+        let count: f64 = self.sample_count as f64;
+
+        self.mean = &self.mean + &((color - &self.mean) / count);
+
+        let luminance: f64 = luminance(color);
+        let delta: f64 = luminance - self.luminance_mean;
+        self.luminance_mean += delta / count;
+        self.luminance_m2 += delta * (luminance - self.luminance_mean);
+    }
+
+    // Combines the statistics of two disjoint sets of samples (Chan et al.)
+    pub fn merge(&mut self, other: &PixelStatistics) {
+        if other.sample_count == 0 {
+            return;
+        }
+
+        let count: f64 = (self.sample_count + other.sample_count) as f64;
+        let other_weight: f64 = other.sample_count as f64 / count;
+        let delta: f64 = other.luminance_mean - self.luminance_mean;
+
+        self.mean = &self.mean + &((&other.mean - &self.mean) * other_weight);
+        self.luminance_mean += delta * other_weight;
+        self.luminance_m2 += other.luminance_m2 + delta * delta * self.sample_count as f64 * other_weight;
+        self.sample_count += other.sample_count;
+    }
+
+    // Standard error of the mean luminance relative to the mean luminance. Very dark pixels are compared against a
+    // luminance of `MIN_RELATIVE_ERROR_LUMINANCE` instead, since any noise is invisible there.
+    pub fn relative_error(&self) -> f64 {
+        if self.sample_count < 2 {
+            return f64::INFINITY;
+        }
+
+        let variance: f64 = (self.luminance_m2 / (self.sample_count - 1) as f64).max(0.0);
+        let standard_error: f64 = (variance / self.sample_count as f64).sqrt();
+        standard_error / self.luminance_mean.max(MIN_RELATIVE_ERROR_LUMINANCE)
+    }
+}
+
+impl Default for PixelStatistics {
+    fn default() -> PixelStatistics {
+        PixelStatistics::new()
+    }
+}
+
+// Statistics of the radiance samples of every pixel, used to combine the samples of multiple passes
+#[derive(Serialize, Deserialize)]
+pub struct AccumulationBuffer {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<PixelStatistics>,
+}
+
+impl AccumulationBuffer {
+    pub fn new(width: u32, height: u32) -> AccumulationBuffer {
+        AccumulationBuffer {
+            width,
+            height,
+            pixels: vec![PixelStatistics::new(); (width * height) as usize],
+        }
+    }
+
+    // Merges the statistics of a tile, given row by row
+    pub fn add_tile(&mut self, tile: &Tile, statistics: Vec<PixelStatistics>) {
+        for (i, pixel_statistics) in statistics.iter().enumerate() {
+            let x: u32 = tile.x + i as u32 % tile.width;
+            let y: u32 = tile.y + i as u32 / tile.width;
+            self.pixels[(y * self.width + x) as usize].merge(pixel_statistics);
+        }
+    }
+
+    // Indices of the samples every pixel renders in the next pass: the next `samples_per_pass` samples up to
+    // `max_samples`. With a `noise_threshold`, pixels with at least `min_samples` samples whose relative error is below
+    // the threshold are converged and get no further samples.
+    pub fn pass_samples(&self, samples_per_pass: u32, max_samples: u32, noise_threshold: Option<f64>, min_samples: u32) -> Vec<Range<u32>> {
+        self.pixels.iter()
+            .map(|pixel| {
+                let count: u32 = pixel.sample_count;
+                let is_converged: bool = noise_threshold
+                    .is_some_and(|threshold| count >= min_samples && pixel.relative_error() <= threshold);
+
+                if is_converged { count..count } else { count..(count + samples_per_pass).min(max_samples).max(count) }
+            })
+            .collect()
+    }
+
+    pub fn to_framebuffer(&self) -> Framebuffer {
+        Framebuffer {
+            width: self.width,
+            height: self.height,
+            pixels: self.pixels.iter().map(|pixel| pixel.mean.clone()).collect(),
+        }
+    }
+
+    // Visualizes the number of samples of every pixel, from blue (fewest) to red (most samples)
+    pub fn sample_count_heatmap(&self) -> Framebuffer {
+        let min_count: u32 = self.pixels.iter().map(|pixel| pixel.sample_count).min().unwrap_or(0);
+        let max_count: u32 = self.pixels.iter().map(|pixel| pixel.sample_count).max().unwrap_or(0);
+        let range: f64 = (max_count - min_count).max(1) as f64;
+
+        Framebuffer {
+            width: self.width,
+            height: self.height,
+            pixels: self.pixels.iter()
+                .map(|pixel| hsv_to_rgb(240.0 * (1.0 - (pixel.sample_count - min_count) as f64 / range), 1.0, 1.0))
+                .map(|color| Vector3 { x: srgb_to_linear(color.x), y: srgb_to_linear(color.y), z: srgb_to_linear(color.z) })
+                .collect(),
+        }
+    }
+}
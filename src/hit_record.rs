@@ -1,23 +1,32 @@
 use crate::{Material, Vector3};
+use crate::material::NoMaterial;
 
-pub struct HitRecord {
+pub struct HitRecord<'a> {
     pub hit: bool,
     pub t: f64,
     pub point: Vector3,
     pub normal: Vector3,
     pub is_front_face: bool,
-    pub material: Material,
+    // texture coordinates of the hit point
+    pub u: f64,
+    pub v: f64,
+    pub material: &'a dyn Material,
+    // index of the hit object in the objects of the scene, filled in by the scene
+    pub object: usize,
 }
 
-impl HitRecord {
-    pub fn no_hit() -> HitRecord {
+impl HitRecord<'_> {
+    pub fn no_hit() -> HitRecord<'static> {
         HitRecord {
             hit: false,
             t: 0.0,
             point: Vector3::zero(),
             normal: Vector3::zero(),
             is_front_face: true,
-            material: Material::NONE,
+            u: 0.0,
+            v: 0.0,
+            material: &NoMaterial,
+            object: 0,
         }
     }
 }
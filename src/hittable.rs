@@ -0,0 +1,31 @@
+use crate::Vector3;
+use crate::hit_record::HitRecord;
+use crate::ray::Ray;
+use crate::aabb::AABB;
+
+pub trait Hittable: Send + Sync {
+    fn ray_hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> HitRecord<'_>;
+
+    fn bounding_box(&self) -> AABB;
+
+    // Objects with an emissive material are sampled directly as lights
+    fn is_emissive(&self) -> bool {
+        false
+    }
+
+    // Estimate of the power emitted by the object, lights are sampled proportionally to it
+    fn emitted_power(&self) -> f64 {
+        0.0
+    }
+
+    // Samples a direction from `origin` towards the object, returning the unit direction and its solid angle probability
+    // density, or `None` if the object can't be sampled
+    fn sample_direction(&self, _origin: &Vector3, _random: &mut fastrand::Rng) -> Option<(Vector3, f64)> {
+        None
+    }
+
+    // Solid angle probability density of `sample_direction` returning `direction`
+    fn direction_pdf(&self, _origin: &Vector3, _direction: &Vector3) -> f64 {
+        0.0
+    }
+}
@@ -0,0 +1,151 @@
+// Minimal ICC v4 display profiles (matrix and tone curve), embedded into low dynamic range output files so that viewers
+// know which color space the stored values are in
+
+// D50 illuminant of the profile connection space
+const D50: [f64; 3] = [0.9642, 1.0, 0.8249];
+
+// Bradford chromatic adaptation from the D65 white point of the display to D50
+const D65_TO_D50: [f64; 9] = [
+    1.047882, 0.022919, -0.050201,
+    0.029587, 0.990479, -0.017059,
+    -0.009232, 0.015076, 0.751678,
+];
+
+// Display P3 primaries in the profile connection space (adapted to D50), as the columns of the RGB to XYZ matrix
+const DISPLAY_P3_COLORANTS: [[f64; 3]; 3] = [
+    [0.515121, 0.241196, -0.001053],
+    [0.291977, 0.692245, 0.041885],
+    [0.157104, 0.066574, 0.784073],
+];
+
+// Rec. 709 (sRGB) primaries in the profile connection space
+const REC709_COLORANTS: [[f64; 3]; 3] = [
+    [0.436066, 0.222488, 0.013916],
+    [0.385147, 0.716873, 0.097076],
+    [0.143066, 0.060608, 0.714096],
+];
+
+// ICC parametric curve of type 3 with the parameters g, a, b, c, d of the sRGB transfer function:
+// Y = (aX + b)^g for X >= d, Y = cX otherwise
+const SRGB_CURVE: (u16, &[f64]) = (3, &[2.4, 1.0 / 1.055, 0.055 / 1.055, 1.0 / 12.92, 0.04045]);
+
+// ICC parametric curve of type 0 with g = 1: Y = X
+const LINEAR_CURVE: (u16, &[f64]) = (0, &[1.0]);
+
+pub fn display_p3_profile() -> Vec<u8> {
+    build_profile("Display P3", &DISPLAY_P3_COLORANTS, SRGB_CURVE)
+}
+
+pub fn linear_rec709_profile() -> Vec<u8> {
+    build_profile("Linear Rec. 709", &REC709_COLORANTS, LINEAR_CURVE)
+}
+
+fn build_profile(description: &str, colorants: &[[f64; 3]; 3], (function_type, parameters): (u16, &[f64])) -> Vec<u8> {
+    let tone_curve: Vec<u8> = parametric_curve(function_type, parameters);
+
+    let tags: [(&[u8; 4], Vec<u8>); 9] = [
+        (b"desc", text(description)),
+        (b"cprt", text("No copyright, use freely")),
+        (b"wtpt", xyz(&D50)),
+        (b"chad", s15_fixed16_array(&D65_TO_D50)),
+        (b"rXYZ", xyz(&colorants[0])),
+        (b"gXYZ", xyz(&colorants[1])),
+        (b"bXYZ", xyz(&colorants[2])),
+        (b"rTRC", tone_curve.clone()),
+        (b"gTRC", tone_curve),
+    ];
+    let tag_count: usize = tags.len() + 1;
+
+    let mut tag_table: Vec<u8> = (tag_count as u32).to_be_bytes().to_vec();
+    let mut tag_data: Vec<u8> = Vec::new();
+    let data_start: usize = 128 + 4 + 12 * tag_count;
+
+    for (signature, data) in &tags {
+        let offset: usize = data_start + tag_data.len();
+        push_tag_entry(&mut tag_table, signature, offset, data.len());
+        // the three tone curves share the same data
+        if *signature == b"gTRC" {
+            push_tag_entry(&mut tag_table, b"bTRC", offset, data.len());
+        }
+
+        tag_data.extend_from_slice(data);
+        // tag data starts on four byte boundaries
+        tag_data.resize(tag_data.len().next_multiple_of(4), 0);
+    }
+
+    let size: usize = data_start + tag_data.len();
+
+    let mut profile: Vec<u8> = Vec::with_capacity(size);
+    profile.extend_from_slice(&(size as u32).to_be_bytes());
+    profile.extend_from_slice(&[0; 4]); // preferred CMM
+    profile.extend_from_slice(&[4, 0x30, 0, 0]); // version 4.3
+    profile.extend_from_slice(b"mntrRGB XYZ ");
+    profile.extend_from_slice(&[0x07, 0xE8, 0, 1, 0, 1, 0, 0, 0, 0, 0, 0]); // creation date 2024-01-01 00:00:00
+    profile.extend_from_slice(b"acsp");
+    profile.extend_from_slice(&[0; 24]); // platform, flags, manufacturer, model and attributes
+    profile.extend_from_slice(&[0; 4]); // perceptual rendering intent
+    for value in D50 {
+        profile.extend_from_slice(&s15_fixed16(value));
+    }
+    profile.extend_from_slice(&[0; 4]); // creator
+    profile.extend_from_slice(&[0; 16]); // profile ID, zero means not computed
+    profile.extend_from_slice(&[0; 28]);
+
+    profile.extend_from_slice(&tag_table);
+    profile.extend_from_slice(&tag_data);
+    profile
+}
+
+fn push_tag_entry(tag_table: &mut Vec<u8>, signature: &[u8; 4], offset: usize, size: usize) {
+    tag_table.extend_from_slice(signature);
+    tag_table.extend_from_slice(&(offset as u32).to_be_bytes());
+    tag_table.extend_from_slice(&(size as u32).to_be_bytes());
+}
+
+// multiLocalizedUnicodeType with a single English record
+fn text(value: &str) -> Vec<u8> {
+    let utf16: Vec<u8> = value.encode_utf16().flat_map(|unit| unit.to_be_bytes()).collect();
+
+    let mut data: Vec<u8> = b"mluc".to_vec();
+    data.extend_from_slice(&[0; 4]);
+    data.extend_from_slice(&1u32.to_be_bytes()); // record count
+    data.extend_from_slice(&12u32.to_be_bytes()); // record size
+    data.extend_from_slice(b"enUS");
+    data.extend_from_slice(&(utf16.len() as u32).to_be_bytes());
+    data.extend_from_slice(&28u32.to_be_bytes()); // offset of the string
+    data.extend_from_slice(&utf16);
+    data
+}
+
+fn xyz(values: &[f64; 3]) -> Vec<u8> {
+    let mut data: Vec<u8> = b"XYZ ".to_vec();
+    data.extend_from_slice(&[0; 4]);
+    for value in values {
+        data.extend_from_slice(&s15_fixed16(*value));
+    }
+    data
+}
+
+fn s15_fixed16_array(values: &[f64]) -> Vec<u8> {
+    let mut data: Vec<u8> = b"sf32".to_vec();
+    data.extend_from_slice(&[0; 4]);
+    for value in values {
+        data.extend_from_slice(&s15_fixed16(*value));
+    }
+    data
+}
+
+fn parametric_curve(function_type: u16, parameters: &[f64]) -> Vec<u8> {
+    let mut data: Vec<u8> = b"para".to_vec();
+    data.extend_from_slice(&[0; 4]);
+    data.extend_from_slice(&function_type.to_be_bytes());
+    data.extend_from_slice(&[0; 2]);
+    for value in parameters {
+        data.extend_from_slice(&s15_fixed16(*value));
+    }
+    data
+}
+
+fn s15_fixed16(value: f64) -> [u8; 4] {
+    ((value * 65536.0).round() as i32).to_be_bytes()
+}
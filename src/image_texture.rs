@@ -0,0 +1,160 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use image::DynamicImage;
+use serde::Deserialize;
+use crate::Vector3;
+use crate::color_util::{decode_pixels, is_linear_format};
+use crate::texture::{ShadingContext, Texture};
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+pub enum TextureFilter {
+    // color of the texel containing the UV coordinates
+    NEAREST,
+    // weighted average of the four closest texels
+    #[default]
+    BILINEAR,
+}
+
+// How UV coordinates outside of [0, 1] are mapped onto the image
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+pub enum WrapMode {
+    #[default]
+    REPEAT,
+    CLAMP,
+    MIRROR,
+}
+
+// Transfer function of the stored values. Color textures are usually sRGB encoded, data like roughness or normal maps
+// and floating point images are linear.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Hash)]
+pub enum TextureEncoding {
+    SRGB,
+    LINEAR,
+}
+
+// Decoded pixels of an image file in linear color, shared between all textures using the same file
+pub struct TextureImage {
+    width: usize,
+    height: usize,
+    pixels: Vec<Vector3>,
+}
+
+// Texture looking up an image by the UV coordinates of the hit point, with `v` pointing upwards in the image. The
+// coordinates are multiplied by `scale` first, so values above 1 tile the image according to the wrap mode.
+pub struct ImageTexture {
+    pub image: Arc<TextureImage>,
+    pub filter: TextureFilter,
+    pub wrap: WrapMode,
+    pub scale: f64,
+}
+
+// Images loaded while building a scene, so that every file is only read once no matter how many textures use it, and
+// only decoded once for each encoding it is used with
+#[derive(Default)]
+pub struct TextureCache {
+    files: HashMap<PathBuf, DynamicImage>,
+    images: HashMap<(PathBuf, TextureEncoding), Arc<TextureImage>>,
+}
+
+impl TextureEncoding {
+    // Encoding of files without an explicit one: floating point formats are linear, all other formats sRGB
+    fn default_of(image: &DynamicImage) -> TextureEncoding {
+        if is_linear_format(image) { TextureEncoding::LINEAR } else { TextureEncoding::SRGB }
+    }
+}
+
+impl TextureImage {
+    // Loads a PNG, JPEG, Radiance HDR, OpenEXR, ... file, in the default encoding of its format unless `encoding` is given
+    pub fn load(path: &Path, encoding: Option<TextureEncoding>) -> Result<TextureImage, String> {
+        let image: DynamicImage = open_image(path)?;
+        Ok(TextureImage::decode(&image, encoding.unwrap_or_else(|| TextureEncoding::default_of(&image))))
+    }
+
+    fn decode(image: &DynamicImage, encoding: TextureEncoding) -> TextureImage {
+        let pixels: Vec<Vector3> = decode_pixels(image, encoding == TextureEncoding::SRGB);
+        TextureImage::from_pixels(image.width() as usize, image.height() as usize, pixels)
+    }
+
+    // Creates an image from linear colors given row by row, starting at the top left corner
+    pub fn from_pixels(width: usize, height: usize, pixels: Vec<Vector3>) -> TextureImage {
+        assert!(width > 0 && height > 0 && pixels.len() == width * height, "texture image size does not match its pixels");
+        TextureImage { width, height, pixels }
+    }
+
+    fn pixel(&self, x: i64, y: i64, wrap: WrapMode) -> &Vector3 {
+        let x: usize = wrap_index(x, self.width, wrap);
+        let y: usize = wrap_index(y, self.height, wrap);
+        &self.pixels[y * self.width + x]
+    }
+}
+
+impl Texture for ImageTexture {
+    fn get_color(&self, context: &ShadingContext) -> Vector3 {
+        let image: &TextureImage = &self.image;
+        let x: f64 = context.u * self.scale * image.width as f64;
+        let y: f64 = (1.0 - context.v * self.scale) * image.height as f64;
+
+        match self.filter {
+            TextureFilter::NEAREST => image.pixel(x.floor() as i64, y.floor() as i64, self.wrap).clone(),
+            TextureFilter::BILINEAR => {
+                // texel centers lie at half-integer coordinates
+                let x: f64 = x - 0.5;
+                let y: f64 = y - 0.5;
+                let x0: f64 = x.floor();
+                let y0: f64 = y.floor();
+                let fx: f64 = x - x0;
+                let fy: f64 = y - y0;
+                let (x0, y0): (i64, i64) = (x0 as i64, y0 as i64);
+
+                let top: Vector3 = image.pixel(x0, y0, self.wrap) * (1.0 - fx) + image.pixel(x0 + 1, y0, self.wrap) * fx;
+                let bottom: Vector3 = image.pixel(x0, y0 + 1, self.wrap) * (1.0 - fx) + image.pixel(x0 + 1, y0 + 1, self.wrap) * fx;
+                top * (1.0 - fy) + bottom * fy
+            }
+        }
+    }
+}
+
+impl TextureCache {
+    pub fn new() -> TextureCache {
+        TextureCache::default()
+    }
+
+    // Returns the already decoded image of the file, or loads it on the first request. Textures that don't specify an
+    // encoding share the image with the ones that explicitly use the default encoding of the file.
+    pub fn load(&mut self, path: &Path, encoding: Option<TextureEncoding>) -> Result<Arc<TextureImage>, String> {
+        // different relative paths to the same file share one entry
+        let path: PathBuf = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+
+        if !self.files.contains_key(&path) {
+            let file: DynamicImage = open_image(&path)?;
+            self.files.insert(path.clone(), file);
+        }
+
+        let file: &DynamicImage = &self.files[&path];
+        let encoding: TextureEncoding = encoding.unwrap_or_else(|| TextureEncoding::default_of(file));
+
+        let image: &Arc<TextureImage> = self.images.entry((path, encoding))
+            .or_insert_with(|| Arc::new(TextureImage::decode(file, encoding)));
+        Ok(Arc::clone(image))
+    }
+}
+
+fn open_image(path: &Path) -> Result<DynamicImage, String> {
+    image::open(path).map_err(|error| format!("Failed to load texture image '{}': {}", path.display(), error))
+}
+
+fn wrap_index(index: i64, size: usize, wrap: WrapMode) -> usize {
+    let size: i64 = size as i64;
+
+    let wrapped: i64 = match wrap {
+        WrapMode::REPEAT => index.rem_euclid(size),
+        WrapMode::CLAMP => index.clamp(0, size - 1),
+        WrapMode::MIRROR => {
+            let period: i64 = index.rem_euclid(2 * size);
+            if period < size { period } else { 2 * size - 1 - period }
+        }
+    };
+
+    wrapped as usize
+}
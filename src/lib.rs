@@ -0,0 +1,40 @@
+// Path tracing renderer. A render is set up from a `Scene` (built in code or loaded with `scene_file::load`), a
+// `Camera` and `RenderSettings`, and run with `Renderer::render`, which accumulates the samples into an
+// `AccumulationBuffer` that can be converted to a `Framebuffer` and saved with `output::save`.
+
+mod aabb;
+mod bvh;
+mod color_util;
+mod distribution;
+mod icc;
+mod triangle;
+
+pub mod background;
+pub mod camera;
+pub mod checkpoint;
+pub mod color_space;
+pub mod framebuffer;
+pub mod hit_record;
+pub mod hittable;
+pub mod image_texture;
+pub mod material;
+pub mod mesh;
+pub mod output;
+pub mod ray;
+pub mod renderer;
+pub mod scatter_info;
+pub mod scene;
+pub mod scene_file;
+pub mod sphere;
+pub mod texture;
+pub mod tiles;
+pub mod tone_mapping;
+pub mod transform;
+pub mod vec3;
+
+pub use crate::camera::Camera;
+pub use crate::material::Material;
+pub use crate::renderer::{Renderer, RenderSettings};
+pub use crate::scene::Scene;
+pub use crate::texture::Texture;
+pub use crate::vec3::Vector3;
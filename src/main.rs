@@ -1,115 +1,455 @@
-mod vec3;
-mod ray;
-mod sphere;
-mod hit_record;
-mod scene;
-mod camera;
-mod material;
-mod scatter_info;
-mod texture;
-mod renderer;
-mod color_util;
-
-use std::sync::{Arc, mpsc, Mutex};
-use std::{thread};
+use std::process;
+use std::sync::atomic::Ordering;
+use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant};
-use image::{ImageBuffer, RgbImage};
 use clap::Parser;
-use crate::camera::Camera;
-use crate::hit_record::HitRecord;
-use crate::material::Material;
-use crate::ray::Ray;
-use crate::renderer::render_row;
-use crate::scatter_info::ScatterInfo;
-use crate::scene::{Scene};
-use crate::sphere::Sphere;
-use crate::vec3::{Vector3};
-
-const IMAGE_WIDTH: u32 = 1920;
-const IMAGE_HEIGHT: u32 = 1080;
-const MAX_RAY_TRACE_DEPTH: u32 = 50;
-const DEFAULT_SAMPLES_PER_PIXEL: u32 = 100;
+use rust_ray_tracing::{Camera, Renderer, RenderSettings, Scene, Vector3};
+use rust_ray_tracing::background::Background;
+use rust_ray_tracing::checkpoint;
+use rust_ray_tracing::checkpoint::CheckpointSettings;
+use rust_ray_tracing::color_space::ColorSpace;
+use rust_ray_tracing::framebuffer::{AccumulationBuffer, Framebuffer};
+use rust_ray_tracing::output;
+use rust_ray_tracing::output::OutputFormat;
+use rust_ray_tracing::renderer::{DEFAULT_IMAGE_HEIGHT, DEFAULT_IMAGE_WIDTH, DEFAULT_MAX_DEPTH, DEFAULT_MIN_SAMPLES,
+                                 DEFAULT_RUSSIAN_ROULETTE_DEPTH, DEFAULT_SAMPLES_PER_PIXEL, DEFAULT_TILE_SIZE, RenderStatus};
+use rust_ray_tracing::scene_file;
+use rust_ray_tracing::tiles::TileOrder;
+use rust_ray_tracing::tone_mapping::ToneMapping;
+
+const DEFAULT_SAMPLES_PER_PASS: u32 = 4;
+// passes of renders that are only checkpointed just have to be short compared to the checkpoint interval, and longer
+// passes make the render threads wait for each other less often
+const DEFAULT_CHECKPOINT_SAMPLES_PER_PASS: u32 = 16;
+const DEFAULT_CHECKPOINT_INTERVAL: f64 = 300.0;
+// the layout of the random spheres scene is the same for every render, independent of --seed
+const RANDOM_SPHERES_SEED: u64 = 0;
 
 #[derive(Parser, Debug)]
 struct Args {
-    /// Amount of samples per pixel to calculate
+    /// Amount of samples per pixel to calculate (the maximum amount with adaptive sampling)
     #[clap(short, long, default_value_t = DEFAULT_SAMPLES_PER_PIXEL)]
     samples_per_pixel: u32,
 
+    /// Maximum number of bounces of a path
+    #[clap(long, default_value_t = DEFAULT_MAX_DEPTH)]
+    max_depth: u32,
+
+    /// Number of bounces after which paths are randomly terminated depending on their throughput
+    #[clap(long, default_value_t = DEFAULT_RUSSIAN_ROULETTE_DEPTH)]
+    russian_roulette_depth: u32,
+
+    /// Render the samples in passes over the whole image and periodically write the intermediate result to the output
+    /// file, so that long renders can be inspected and stopped early
+    #[clap(long)]
+    progressive: bool,
+
+    /// Number of samples per pixel rendered in each pass of a progressive, adaptive, time limited or checkpointed
+    /// render (4 if omitted, 16 for renders that are only checkpointed)
+    #[clap(long)]
+    samples_per_pass: Option<u32>,
+
+    /// Write the intermediate image of a progressive render after this many passes
+    #[clap(long)]
+    update_passes: Option<u32>,
+
+    /// Write the intermediate image of a progressive render after this many seconds. If neither this nor
+    /// --update-passes is given, the image is written after every pass
+    #[clap(long)]
+    update_interval: Option<f64>,
+
+    /// Enable adaptive sampling: pixels stop receiving samples once the standard error of their mean luminance,
+    /// relative to the luminance, falls below this threshold (e.g. 0.02). Samples are rendered in passes of
+    /// --samples-per-pass
+    #[clap(long)]
+    noise_threshold: Option<f64>,
+
+    /// Number of samples every pixel receives before adaptive sampling may consider it converged
+    #[clap(long, default_value_t = DEFAULT_MIN_SAMPLES)]
+    min_samples: u32,
+
+    /// Path of an image showing the number of samples of every pixel, from blue (fewest) to red (most)
+    #[clap(long)]
+    sample_heatmap: Option<PathBuf>,
+
+    /// Stop rendering after this many seconds (measured from the start of the program) and save the samples rendered
+    /// so far. The samples are rendered in passes of --samples-per-pass, so that every pixel has samples when the render
+    /// stops
+    #[clap(long)]
+    time_limit: Option<f64>,
+
+    /// Periodically save the accumulated samples to this checkpoint file, so that the render can be continued with
+    /// --resume after an interruption
+    #[clap(long)]
+    checkpoint: Option<PathBuf>,
+
+    /// Seconds between two checkpoints, a checkpoint is also saved when the render finishes or is stopped
+    #[clap(long, default_value_t = DEFAULT_CHECKPOINT_INTERVAL)]
+    checkpoint_interval: f64,
+
+    /// Continue the render stored in a checkpoint file until every pixel has --samples-per-pixel samples, which also
+    /// allows adding samples to a finished render. The scene, image size, seed and path settings are taken from the
+    /// checkpoint. Further checkpoints are saved to the same file unless --checkpoint is given. The scene file must not
+    /// have changed since the checkpoint was saved, the meshes, textures and environment images it uses are not checked
+    #[clap(long)]
+    resume: Option<PathBuf>,
+
     /// Number of render threads
     #[clap(short, long, default_value_t = num_cpus::get() as u32)]
     threads: u32,
+
+    /// Edge length of the square tiles the image is split into for rendering
+    #[clap(long, default_value_t = DEFAULT_TILE_SIZE)]
+    tile_size: u32,
+
+    /// Order in which the tiles are rendered
+    #[clap(long, value_enum, default_value_t = TileOrder::SPIRAL)]
+    tile_order: TileOrder,
+
+    /// Seed of the random number generator. Renders with the same seed and settings are identical regardless of the
+    /// number of threads (a random seed is chosen if omitted)
+    #[clap(long)]
+    seed: Option<u64>,
+
+    /// Width of the rendered image in pixels
+    #[clap(long, default_value_t = DEFAULT_IMAGE_WIDTH)]
+    width: u32,
+
+    /// Height of the rendered image in pixels
+    #[clap(long, default_value_t = DEFAULT_IMAGE_HEIGHT)]
+    height: u32,
+
+    /// Test every object for each ray instead of traversing the bounding volume hierarchy
+    #[clap(long)]
+    no_bvh: bool,
+
+    /// Path to a TOML scene description file (renders the built-in random spheres scene if omitted)
+    #[clap(long)]
+    scene: Option<String>,
+
+    /// Background of the scene: sky, black, solid:R,G,B, gradient:R,G,B:R,G,B (horizon and zenith) or image:PATH
+    /// (equirectangular). Overrides the background of the scene file
+    #[clap(long)]
+    background: Option<String>,
+
+    /// Rotation of an image background around the vertical axis in degrees. Applies to image backgrounds given by
+    /// --background or the scene file, overriding the rotation of the scene file
+    #[clap(long, allow_negative_numbers = true)]
+    environment_rotation: Option<f64>,
+
+    /// Intensity multiplier of an image background. Applies to image backgrounds given by --background or the scene
+    /// file, overriding the intensity of the scene file
+    #[clap(long)]
+    environment_intensity: Option<f64>,
+
+    /// Path of the rendered image. The format is inferred from the extension: png, jpg, tiff and bmp store the
+    /// tone mapped image, the high dynamic range formats exr, hdr and pfm store the linear radiance
+    #[clap(short, long, default_value = "render.png")]
+    output: PathBuf,
+
+    /// Replace the output file if it already exists
+    #[clap(long)]
+    overwrite: bool,
+
+    /// Tone mapping operator used for low dynamic range output formats
+    #[clap(long, value_enum, default_value_t = ToneMapping::CLAMP)]
+    tone_mapping: ToneMapping,
+
+    /// Exposure adjustment in stops (EV) applied before tone mapping
+    #[clap(long, default_value_t = 0.0, allow_negative_numbers = true)]
+    exposure: f64,
+
+    /// Luminance that is mapped to white by the extended Reinhard operator
+    #[clap(long, default_value_t = 4.0)]
+    white_point: f64,
+
+    /// Color space of low dynamic range output formats, high dynamic range formats always store linear Rec. 709. Display P3
+    /// and linear Rec. 709 output embeds an ICC profile and requires png, jpg or tiff
+    #[clap(long, value_enum, default_value_t = ColorSpace::SRGB)]
+    color_space: ColorSpace,
+
+    /// Add noise before quantizing low dynamic range output formats to avoid banding
+    #[clap(long)]
+    dither: bool,
+}
+
+impl Args {
+    fn apply_checkpoint_settings(&mut self, checkpoint_settings: CheckpointSettings) {
+        self.seed = Some(checkpoint_settings.seed);
+        self.width = checkpoint_settings.width;
+        self.height = checkpoint_settings.height;
+        self.max_depth = checkpoint_settings.max_depth;
+        self.russian_roulette_depth = checkpoint_settings.russian_roulette_depth;
+        self.scene = checkpoint_settings.scene;
+        self.background = checkpoint_settings.background;
+        self.environment_rotation = checkpoint_settings.environment_rotation;
+        self.environment_intensity = checkpoint_settings.environment_intensity;
+    }
 }
 
 fn main() {
+    let start_time = Instant::now();
     let mut args: Args = Args::parse();
+
+    let mut resumed_scene_hash: Option<u64> = None;
+    let resumed_accumulation_buffer: Option<AccumulationBuffer> = args.resume.clone().map(|path| {
+        let (checkpoint_settings, accumulation_buffer) = checkpoint::load(&path).unwrap_or_else(|error| {
+            eprintln!("{}", error);
+            process::exit(1);
+        });
+
+        resumed_scene_hash = checkpoint_settings.scene_hash;
+        args.apply_checkpoint_settings(checkpoint_settings);
+        args.checkpoint = args.checkpoint.take().or(Some(path));
+        accumulation_buffer
+    });
+
+    // checkpoints store the canonical paths of the scene file and of an image background, so that they can be resumed
+    // from any directory
+    if let Some(image_path) = args.background.as_deref().and_then(|background| background.strip_prefix("image:")) {
+        let path: PathBuf = Path::new(image_path).canonicalize().unwrap_or_else(|error| {
+            eprintln!("Failed to load environment image '{}': {}", image_path, error);
+            process::exit(1);
+        });
+        args.background = Some(format!("image:{}", path.display()));
+    }
+
+    let mut scene_hash: Option<u64> = None;
+    if let Some(scene) = &args.scene {
+        let path: PathBuf = Path::new(scene).canonicalize().unwrap_or_else(|error| {
+            eprintln!("Failed to read scene file '{}': {}", scene, error);
+            process::exit(1);
+        });
+        scene_hash = Some(checkpoint::hash_file(&path).unwrap_or_else(|error| {
+            eprintln!("{}", error);
+            process::exit(1);
+        }));
+        args.scene = Some(path.to_string_lossy().into_owned());
+    }
+
+    if args.resume.is_some() && scene_hash != resumed_scene_hash {
+        eprintln!("Scene file '{}' has changed since the checkpoint was saved", args.scene.as_deref().unwrap_or_default());
+        process::exit(1);
+    }
+
     args.threads = args.threads.max(1);
 
-    let camera = Arc::new(Camera::new(
-        Vector3 { x: 12.0, y: 2.0, z: -3.0 },
-        Vector3 { x: 0.0, y: 0.0, z: 0.0 },
-        25.0,
-        0.0,
-        10.0,
-    ));
+    let is_rendered_in_passes: bool = args.progressive || args.noise_threshold.is_some() || args.checkpoint.is_some() || args.time_limit.is_some();
+    if args.samples_per_pass.is_some() && !is_rendered_in_passes {
+        eprintln!("--samples-per-pass requires --progressive, --noise-threshold, --checkpoint or --time-limit");
+        process::exit(1);
+    }
+    let default_samples_per_pass: u32 = if args.progressive || args.noise_threshold.is_some() || args.time_limit.is_some() { DEFAULT_SAMPLES_PER_PASS } else { DEFAULT_CHECKPOINT_SAMPLES_PER_PASS };
+    let samples_per_pass: u32 = args.samples_per_pass.unwrap_or(default_samples_per_pass).clamp(1, args.samples_per_pixel.max(1));
 
-    let scene = Arc::new(Scene::generate());
+    let output_format: OutputFormat = OutputFormat::from_path(&args.output).unwrap_or_else(|error| {
+        eprintln!("{}", error);
+        process::exit(1);
+    });
 
-    let mut image: RgbImage = ImageBuffer::new(IMAGE_WIDTH, IMAGE_HEIGHT);
+    if !output_format.is_high_dynamic_range() && args.color_space.icc_profile().is_some() && !output_format.can_store_icc_profile() {
+        eprintln!("Output format of '{}' can't store the color profile of {:?}, use png, jpg or tiff", args.output.display(), args.color_space);
+        process::exit(1);
+    }
 
-    let next_row = Arc::new(Mutex::new(0));
-    let (rows_sender, rows_receiver) = mpsc::channel();
-    let mut handles = Vec::new();
+    let heatmap_format: Option<OutputFormat> = args.sample_heatmap.as_ref().map(|path| OutputFormat::from_path(path).unwrap_or_else(|error| {
+        eprintln!("{}", error);
+        process::exit(1);
+    }));
 
-    let render_start_time = Instant::now();
+    // the resumed checkpoint is expected to be updated
+    let checkpoint_to_replace: Option<&PathBuf> = args.checkpoint.as_ref().filter(|path| Some(*path) != args.resume.as_ref());
 
-    for _ in 0..args.threads {
-        let thread_rows_sender = rows_sender.clone();
-        let thread_next_row = Arc::clone(&next_row);
-        let thread_scene = Arc::clone(&scene);
-        let thread_camera = Arc::clone(&camera);
+    for path in [Some(&args.output), args.sample_heatmap.as_ref(), checkpoint_to_replace].into_iter().flatten() {
+        if path.exists() && !args.overwrite {
+            eprintln!("Output file '{}' already exists, use --overwrite to replace it", path.display());
+            process::exit(1);
+        }
+    }
 
-        let handle = thread::spawn(move || {
-            loop {
-                let mut next_row = thread_next_row.lock().unwrap();
-                if *next_row >= IMAGE_HEIGHT {
-                    break;
-                }
+    let aspect_ratio: f64 = args.width as f64 / args.height as f64;
 
-                let y = *next_row;
-                *next_row += 1;
-                drop(next_row);
+    let seed: u64 = args.seed.unwrap_or_else(|| fastrand::u64(..));
+    println!("Seed: {}", seed);
 
-                println!("{} / {} ({:.2}%)", y + 1, IMAGE_HEIGHT, ((y + 1) as f64) * 100.0 / IMAGE_HEIGHT as f64);
+    let (camera, mut scene) = match &args.scene {
+        Some(path) => scene_file::load(path, aspect_ratio).unwrap_or_else(|error| {
+            eprintln!("{}", error);
+            process::exit(1);
+        }),
+        None => (
+            Camera::new(
+                Vector3 { x: 12.0, y: 2.0, z: -3.0 },
+                Vector3 { x: 0.0, y: 0.0, z: 0.0 },
+                25.0,
+                0.0,
+                10.0,
+                aspect_ratio,
+            ),
+            Scene::generate(RANDOM_SPHERES_SEED),
+        ),
+    };
 
-                let row = render_row(y, args.samples_per_pixel, &thread_camera, &thread_scene);
-                thread_rows_sender.send((y, row)).unwrap();
-            }
+    if let Some(background) = &args.background {
+        scene.background = Background::parse(background).unwrap_or_else(|error| {
+            eprintln!("{}", error);
+            process::exit(1);
         });
+    }
+
+    if args.environment_rotation.is_some() || args.environment_intensity.is_some() {
+        let Background::IMAGE(environment_map) = &mut scene.background else {
+            eprintln!("--environment-rotation and --environment-intensity require an image background");
+            process::exit(1);
+        };
 
-        handles.push(handle);
+        if let Some(rotation) = args.environment_rotation {
+            environment_map.set_rotation(rotation);
+        }
+        if let Some(intensity) = args.environment_intensity {
+            environment_map.set_intensity(intensity);
+        }
     }
 
-    for handle in handles {
-        handle.join().unwrap();
+    if !args.no_bvh {
+        scene.build_bvh();
     }
 
-    let elapsed_render_time: Duration = render_start_time.elapsed();
-    println!("Rendered {} samples/pixel with {} threads in {} ms",
-             args.samples_per_pixel, args.threads, elapsed_render_time.as_millis());
+    let settings: RenderSettings = RenderSettings {
+        image_width: args.width,
+        image_height: args.height,
+        samples_per_pixel: args.samples_per_pixel,
+        samples_per_pass: if is_rendered_in_passes { samples_per_pass } else { args.samples_per_pixel },
+        noise_threshold: args.noise_threshold,
+        min_samples: args.min_samples,
+        max_depth: args.max_depth,
+        russian_roulette_depth: args.russian_roulette_depth,
+        seed,
+        threads: args.threads,
+        tile_size: args.tile_size,
+        tile_order: args.tile_order,
+        deadline: args.time_limit.map(|seconds| start_time + Duration::from_secs_f64(seconds.max(0.0))),
+    };
+
+    let renderer: Renderer = Renderer::new(settings, camera, scene).unwrap_or_else(|error| {
+        eprintln!("{}", error);
+        process::exit(1);
+    });
+
+    // the first Ctrl-C finishes the tiles in progress and saves the partial image, a second one aborts immediately
+    let cancelled = renderer.cancellation_flag();
+    ctrlc::set_handler(move || {
+        if cancelled.swap(true, Ordering::Relaxed) {
+            process::exit(130);
+        }
+
+        eprintln!("Stopping after the tiles in progress, press Ctrl-C again to abort");
+    }).unwrap_or_else(|error| eprintln!("Warning: failed to set Ctrl-C handler: {}", error));
+
+    let mut accumulation_buffer: AccumulationBuffer = resumed_accumulation_buffer
+        .unwrap_or_else(|| AccumulationBuffer::new(args.width, args.height));
+
+    let mut passes_since_update: u32 = 0;
+    let mut last_update_time = Instant::now();
+    let mut last_checkpoint_time = Instant::now();
+
+    let render_start_time = Instant::now();
+
+    let status: RenderStatus = renderer.render(&mut accumulation_buffer, |progress, accumulation_buffer| {
+        if !progress.is_pass_finished {
+            println!("{} / {} ({:.2}%)", progress.finished_tiles, progress.total_tiles,
+                     progress.finished_tiles as f64 * 100.0 / progress.total_tiles as f64);
+            return;
+        }
 
-    let mut rows_processed: u32 = 0;
+        let is_last_pass: bool = progress.pass + 1 >= progress.pass_count;
 
-    for (y, row) in rows_receiver {
-        for x in 0..row.len() {
-            image.put_pixel(x as u32, y, row[x]);
+        passes_since_update += 1;
+        let is_update_due: bool = match (args.update_passes, args.update_interval) {
+            (None, None) => true,
+            (passes, interval) => passes.is_some_and(|passes| passes_since_update >= passes)
+                || interval.is_some_and(|interval| last_update_time.elapsed().as_secs_f64() >= interval),
+        };
+
+        if args.progressive && !is_last_pass && is_update_due {
+            save_image(accumulation_buffer.to_framebuffer(), &args, output_format, seed);
+            println!("Saved intermediate image after pass {} / {}", progress.pass + 1, progress.pass_count);
+
+            passes_since_update = 0;
+            last_update_time = Instant::now();
+        }
+
+        if args.checkpoint.is_some() && !is_last_pass && last_checkpoint_time.elapsed().as_secs_f64() >= args.checkpoint_interval {
+            save_checkpoint(&args, seed, scene_hash, accumulation_buffer);
+            last_checkpoint_time = Instant::now();
         }
+    });
+
+    match status {
+        RenderStatus::FINISHED => {}
+        RenderStatus::CONVERGED => println!("All pixels converged"),
+        RenderStatus::STOPPED => println!("Stopped rendering"),
+    }
+
+    let elapsed_render_time: Duration = render_start_time.elapsed();
+    if args.noise_threshold.is_some() || status == RenderStatus::STOPPED {
+        let total_samples: u64 = accumulation_buffer.pixels.iter().map(|pixel| pixel.sample_count as u64).sum();
+        println!("Rendered {:.2} samples/pixel on average with {} threads in {} ms",
+                 total_samples as f64 / accumulation_buffer.pixels.len() as f64, args.threads, elapsed_render_time.as_millis());
+    } else {
+        println!("Rendered {} samples/pixel with {} threads in {} ms",
+                 args.samples_per_pixel, args.threads, elapsed_render_time.as_millis());
+    }
+
+    if args.checkpoint.is_some() {
+        save_checkpoint(&args, seed, scene_hash, &accumulation_buffer);
+    }
 
-        rows_processed += 1;
-        if rows_processed >= IMAGE_HEIGHT {
-            break;
+    save_image(accumulation_buffer.to_framebuffer(), &args, output_format, seed);
+
+    if let (Some(path), Some(format)) = (&args.sample_heatmap, heatmap_format) {
+        // sRGB output stores the heatmap colors as they were picked and needs no color profile, so any format works
+        if let Err(error) = output::save(&accumulation_buffer.sample_count_heatmap(), format, path, ColorSpace::SRGB, None) {
+            eprintln!("{}", error);
+            process::exit(1);
         }
     }
+}
+
+fn save_checkpoint(args: &Args, seed: u64, scene_hash: Option<u64>, accumulation_buffer: &AccumulationBuffer) {
+    let Some(path) = &args.checkpoint else { return };
 
-    image.save("render.png").unwrap();
+    let checkpoint_settings: CheckpointSettings = CheckpointSettings {
+        seed,
+        width: args.width,
+        height: args.height,
+        max_depth: args.max_depth,
+        russian_roulette_depth: args.russian_roulette_depth,
+        scene: args.scene.clone(),
+        scene_hash,
+        background: args.background.clone(),
+        environment_rotation: args.environment_rotation,
+        environment_intensity: args.environment_intensity,
+    };
+
+    match checkpoint::save(path, &checkpoint_settings, accumulation_buffer) {
+        Ok(()) => println!("Saved checkpoint to '{}'", path.display()),
+        Err(error) => eprintln!("{}", error),
+    }
+}
+
+// Tone maps (for low dynamic range formats) and writes the image to the output file, exits on failure
+fn save_image(mut framebuffer: Framebuffer, args: &Args, output_format: OutputFormat, seed: u64) {
+    if !output_format.is_high_dynamic_range() {
+        framebuffer = args.tone_mapping.apply(&framebuffer, args.exposure, args.white_point);
+    }
+
+    // the dithering noise is drawn from the render seed as well to keep the output reproducible
+    let mut dither_random: fastrand::Rng = fastrand::Rng::with_seed(seed);
+    let dither: Option<&mut fastrand::Rng> = if args.dither { Some(&mut dither_random) } else { None };
+
+    if let Err(error) = output::save(&framebuffer, output_format, &args.output, args.color_space, dither) {
+        eprintln!("{}", error);
+        process::exit(1);
+    }
 }
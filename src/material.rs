@@ -1,27 +1,67 @@
-use crate::{HitRecord, Ray, Vector3};
+use std::f64::consts::PI;
+use std::sync::Arc;
+use crate::Vector3;
+use crate::color_util::luminance;
+use crate::hit_record::HitRecord;
+use crate::ray::Ray;
 use crate::scatter_info::ScatterInfo;
-use crate::texture::Texture;
-
-#[derive(Clone)]
-pub enum Material {
-    NONE,
-    DIFFUSE(Texture),
-    METAL(Texture, f64),
-    DIELECTRIC(f64),
-}
+use crate::texture::{ShadingContext, Texture};
+
+const EMISSION_GRID_SIZE: usize = 8;
+
+// Surface scattering model of an object. Objects share their materials through an `Arc`, hit records only borrow them.
+// New materials only need to implement `scatter`, the other methods default to a specular, non-emissive surface.
+pub trait Material: Send + Sync {
+    // Samples the direction the ray continues in after hitting the surface
+    fn scatter(&self, ray: &Ray, hit_record: &HitRecord, random: &mut fastrand::Rng) -> ScatterInfo;
 
-impl Material {
-    pub fn scatter(&self, ray: &Ray, hit_record: &HitRecord) -> ScatterInfo {
-        return match self {
-            Material::NONE => ScatterInfo::no_scatter(),
-            Material::DIFFUSE(texture) => Material::scatter_diffuse(hit_record, texture),
-            Material::METAL(texture, fuzz) => Material::scatter_metal(ray, hit_record, texture, *fuzz),
-            Material::DIELECTRIC(refraction_index) => Material::scatter_dielectric(ray, hit_record, *refraction_index)
-        };
+    // BSDF value times the cosine term for light arriving from `direction`. Only defined for non-specular materials,
+    // specular ones can't be hit by sampling a light and return zero.
+    fn eval(&self, _hit_record: &HitRecord, _direction: &Vector3) -> Vector3 {
+        Vector3::zero()
     }
 
-    fn scatter_diffuse(hit_record: &HitRecord, texture: &Texture) -> ScatterInfo {
-        let mut scatter_direction: Vector3 = hit_record.normal.clone() + Vector3::random_unit_vector();
+    // Probability density of `scatter` choosing `direction`, or zero for specular materials
+    fn pdf(&self, _hit_record: &HitRecord, _direction: &Vector3) -> f64 {
+        0.0
+    }
+
+    fn emitted(&self, _hit_record: &HitRecord) -> Vector3 {
+        Vector3::zero()
+    }
+
+    // Average luminance of the emitted radiance over the surface, weighting how often lights are sampled
+    fn emitted_luminance(&self) -> f64 {
+        0.0
+    }
+
+    // Objects with an emissive material are sampled directly as lights
+    fn is_emissive(&self) -> bool {
+        false
+    }
+}
+
+pub struct Diffuse {
+    pub texture: Arc<dyn Texture>,
+}
+
+pub struct Metal {
+    pub texture: Arc<dyn Texture>,
+    pub fuzz: f64,
+}
+
+pub struct Dielectric {
+    pub refraction_index: f64,
+}
+
+pub struct Emissive {
+    pub texture: Arc<dyn Texture>,
+    pub intensity: f64,
+}
+
+impl Material for Diffuse {
+    fn scatter(&self, _ray: &Ray, hit_record: &HitRecord, random: &mut fastrand::Rng) -> ScatterInfo {
+        let mut scatter_direction: Vector3 = hit_record.normal.clone() + Vector3::random_unit_vector(random);
 
         if scatter_direction.is_near_zero() {
             scatter_direction = hit_record.normal.clone();
@@ -29,7 +69,7 @@ impl Material {
 
         ScatterInfo {
             does_scatter: true,
-            attenuation: texture.get_color(&hit_record.point),
+            attenuation: self.texture.get_color(&ShadingContext::from_hit_record(hit_record)),
             scattered_ray: Ray {
                 origin: hit_record.point.clone(),
                 direction: scatter_direction,
@@ -37,23 +77,36 @@ impl Material {
         }
     }
 
-    fn scatter_metal(ray: &Ray, hit_record: &HitRecord, texture: &Texture, fuzz: f64) -> ScatterInfo {
-        let scatter_direction: Vector3 = Material::reflect(&ray.direction.normalized(), &hit_record.normal)
-            + Vector3::random_unit_vector() * fuzz;
+    fn eval(&self, hit_record: &HitRecord, direction: &Vector3) -> Vector3 {
+        let cos_theta: f64 = hit_record.normal.dot(&direction.normalized()).max(0.0);
+        self.texture.get_color(&ShadingContext::from_hit_record(hit_record)) * (cos_theta / PI)
+    }
+
+    fn pdf(&self, hit_record: &HitRecord, direction: &Vector3) -> f64 {
+        hit_record.normal.dot(&direction.normalized()).max(0.0) / PI
+    }
+}
+
+impl Material for Metal {
+    fn scatter(&self, ray: &Ray, hit_record: &HitRecord, random: &mut fastrand::Rng) -> ScatterInfo {
+        let scatter_direction: Vector3 = reflect(&ray.direction.normalized(), &hit_record.normal)
+            + Vector3::random_unit_vector(random) * self.fuzz;
 
         ScatterInfo {
             does_scatter: scatter_direction.dot(&hit_record.normal) > 0.0,
-            attenuation: texture.get_color(&hit_record.point),
+            attenuation: self.texture.get_color(&ShadingContext::from_hit_record(hit_record)),
             scattered_ray: Ray {
                 origin: hit_record.point.clone(),
                 direction: scatter_direction,
             },
         }
     }
+}
 
-    fn scatter_dielectric(ray: &Ray, hit_record: &HitRecord, refraction_index: f64) -> ScatterInfo {
-        let refraction_ratio: f64 = if hit_record.is_front_face { 1.0 / refraction_index } else { refraction_index };
-        let scatter_direction: Vector3 = Material::refract(&ray.direction.normalized(), &hit_record.normal, refraction_ratio);
+impl Material for Dielectric {
+    fn scatter(&self, ray: &Ray, hit_record: &HitRecord, random: &mut fastrand::Rng) -> ScatterInfo {
+        let refraction_ratio: f64 = if hit_record.is_front_face { 1.0 / self.refraction_index } else { self.refraction_index };
+        let scatter_direction: Vector3 = refract(&ray.direction.normalized(), &hit_record.normal, refraction_ratio, random);
 
         ScatterInfo {
             does_scatter: true,
@@ -64,24 +117,68 @@ impl Material {
             },
         }
     }
+}
 
-    fn reflect(vector: &Vector3, normal: &Vector3) -> Vector3 {
-        vector - &(normal * (2.0 * vector.dot(normal)))
+impl Material for Emissive {
+    fn scatter(&self, _ray: &Ray, _hit_record: &HitRecord, _random: &mut fastrand::Rng) -> ScatterInfo {
+        ScatterInfo::no_scatter()
     }
 
-    fn refract(vector: &Vector3, normal: &Vector3, refraction_ratio: f64) -> Vector3 {
-        let cos_theta: f64 = normal.dot(&-vector).min(1.0);
-        let sin_theta: f64 = (1.0 - cos_theta * cos_theta).sqrt();
+    fn emitted(&self, hit_record: &HitRecord) -> Vector3 {
+        if hit_record.is_front_face { self.texture.get_color(&ShadingContext::from_hit_record(hit_record)) * self.intensity } else { Vector3::zero() }
+    }
 
-        let r0: f64 = (1.0 - refraction_ratio) / (1.0 + refraction_ratio);
-        let reflectance: f64 = r0 * r0 + (1.0 - r0 * r0) * (1.0 - cos_theta).powi(5);
+    // Textures can only be looked up at single points, so the average is estimated on a grid of texture coordinates
+    fn emitted_luminance(&self) -> f64 {
+        let point: Vector3 = Vector3::zero();
+        let normal: Vector3 = Vector3::up();
+        let mut luminance_sum: f64 = 0.0;
 
-        if refraction_ratio * sin_theta > 1.0 || reflectance > fastrand::f64() {
-            return Material::reflect(vector, normal);
+        for i in 0..EMISSION_GRID_SIZE {
+            for j in 0..EMISSION_GRID_SIZE {
+                let context: ShadingContext = ShadingContext {
+                    point: &point,
+                    normal: &normal,
+                    u: (i as f64 + 0.5) / EMISSION_GRID_SIZE as f64,
+                    v: (j as f64 + 0.5) / EMISSION_GRID_SIZE as f64,
+                };
+                luminance_sum += luminance(&self.texture.get_color(&context)).max(0.0);
+            }
         }
 
-        let r_out_perpendicular: Vector3 = (vector + &(normal * cos_theta)) * refraction_ratio;
-        let r_out_parallel: Vector3 = normal * -(1.0 - r_out_perpendicular.length_squared()).sqrt();
-        r_out_perpendicular + r_out_parallel
+        luminance_sum / (EMISSION_GRID_SIZE * EMISSION_GRID_SIZE) as f64 * self.intensity
+    }
+
+    fn is_emissive(&self) -> bool {
+        true
     }
 }
+
+// Material of hit records that did not hit anything
+pub struct NoMaterial;
+
+impl Material for NoMaterial {
+    fn scatter(&self, _ray: &Ray, _hit_record: &HitRecord, _random: &mut fastrand::Rng) -> ScatterInfo {
+        ScatterInfo::no_scatter()
+    }
+}
+
+fn reflect(vector: &Vector3, normal: &Vector3) -> Vector3 {
+    vector - &(normal * (2.0 * vector.dot(normal)))
+}
+
+fn refract(vector: &Vector3, normal: &Vector3, refraction_ratio: f64, random: &mut fastrand::Rng) -> Vector3 {
+    let cos_theta: f64 = normal.dot(&-vector).min(1.0);
+    let sin_theta: f64 = (1.0 - cos_theta * cos_theta).sqrt();
+
+    let r0: f64 = (1.0 - refraction_ratio) / (1.0 + refraction_ratio);
+    let reflectance: f64 = r0 * r0 + (1.0 - r0 * r0) * (1.0 - cos_theta).powi(5);
+
+    if refraction_ratio * sin_theta > 1.0 || reflectance > random.f64() {
+        return reflect(vector, normal);
+    }
+
+    let r_out_perpendicular: Vector3 = (vector + &(normal * cos_theta)) * refraction_ratio;
+    let r_out_parallel: Vector3 = normal * -(1.0 - r_out_perpendicular.length_squared()).sqrt();
+    r_out_perpendicular + r_out_parallel
+}
@@ -0,0 +1,126 @@
+use std::path::Path;
+use std::sync::Arc;
+use crate::{Material, Vector3};
+use crate::hittable::Hittable;
+use crate::material::{Dielectric, Diffuse, Emissive, Metal};
+use crate::texture::SolidColor;
+use crate::transform::Transform;
+use crate::triangle::Triangle;
+
+const DEFAULT_DIFFUSE_COLOR: Vector3 = Vector3 { x: 0.8, y: 0.8, z: 0.8 };
+const DEFAULT_REFRACTION_INDEX: f64 = 1.5;
+
+pub struct Mesh {
+    pub positions: Vec<Vector3>,
+    pub normals: Vec<Vector3>,
+    pub texture_coordinates: Vec<(f64, f64)>,
+    pub indices: Vec<[usize; 3]>,
+    pub material: Arc<dyn Material>,
+}
+
+impl Mesh {
+    pub fn into_triangles(self) -> Vec<Box<dyn Hittable>> {
+        let face_count: usize = self.indices.len();
+        let mesh: Arc<Mesh> = Arc::new(self);
+
+        (0..face_count)
+            .map(|face| Box::new(Triangle { mesh: Arc::clone(&mesh), face }) as Box<dyn Hittable>)
+            .collect()
+    }
+}
+
+// Loads all models of a Wavefront OBJ file as triangles. Materials are taken from the referenced MTL file unless
+// `material_override` is given.
+pub fn load_obj(path: &Path, transform: &Transform, material_override: Option<&Arc<dyn Material>>) -> Result<Vec<Box<dyn Hittable>>, String> {
+    let (models, mtl_materials) = tobj::load_obj(path, &tobj::GPU_LOAD_OPTIONS)
+        .map_err(|error| format!("Failed to load OBJ file '{}': {}", path.display(), error))?;
+
+    let mtl_materials: Vec<tobj::Material> = match mtl_materials {
+        Ok(materials) => materials,
+        Err(error) => {
+            if material_override.is_none() {
+                eprintln!("Warning: failed to load materials of '{}' ({}), using default material", path.display(), error);
+            }
+            Vec::new()
+        }
+    };
+
+    let mut triangles: Vec<Box<dyn Hittable>> = Vec::new();
+
+    for model in models {
+        let obj_mesh: tobj::Mesh = model.mesh;
+
+        let material: Arc<dyn Material> = match (material_override, obj_mesh.material_id.and_then(|id| mtl_materials.get(id))) {
+            (Some(material), _) => Arc::clone(material),
+            (None, Some(mtl_material)) => material_from_mtl(mtl_material),
+            (None, None) => Arc::new(Diffuse { texture: Arc::new(SolidColor { color: DEFAULT_DIFFUSE_COLOR }) }),
+        };
+
+        let mesh: Mesh = Mesh {
+            positions: obj_mesh.positions.chunks_exact(3)
+                .map(|p| transform.apply_to_point(&Vector3 { x: p[0] as f64, y: p[1] as f64, z: p[2] as f64 }))
+                .collect(),
+            normals: obj_mesh.normals.chunks_exact(3)
+                .map(|n| transform.apply_to_normal(&Vector3 { x: n[0] as f64, y: n[1] as f64, z: n[2] as f64 }))
+                .collect(),
+            texture_coordinates: obj_mesh.texcoords.chunks_exact(2)
+                .map(|uv| (uv[0] as f64, uv[1] as f64))
+                .collect(),
+            indices: obj_mesh.indices.chunks_exact(3)
+                .map(|i| [i[0] as usize, i[1] as usize, i[2] as usize])
+                .collect(),
+            material,
+        };
+
+        triangles.append(&mut mesh.into_triangles());
+    }
+
+    Ok(triangles)
+}
+
+// Maps the MTL illumination model onto the closest material: materials with an emission color (`Ke`) become emissive,
+// transparent models (and any dissolve below 1) become dielectrics, models with ray traced reflections become metals
+// and everything else is diffuse.
+fn material_from_mtl(mtl_material: &tobj::Material) -> Arc<dyn Material> {
+    if let Some(emission) = mtl_material.unknown_param.get("Ke").and_then(|value| parse_color(value)) {
+        if !emission.is_near_zero() {
+            return Arc::new(Emissive { texture: Arc::new(SolidColor { color: emission }), intensity: 1.0 });
+        }
+    }
+
+    let diffuse: Vector3 = mtl_material.diffuse.map(to_vector).unwrap_or(DEFAULT_DIFFUSE_COLOR);
+    let illumination_model: u8 = mtl_material.illumination_model.unwrap_or(2);
+
+    if mtl_material.dissolve.is_some_and(|dissolve| dissolve < 1.0) || matches!(illumination_model, 4 | 6 | 7 | 9) {
+        let refraction_index: f64 = mtl_material.optical_density
+            .map(|density| density as f64)
+            .filter(|density| *density > 0.0)
+            .unwrap_or(DEFAULT_REFRACTION_INDEX);
+
+        return Arc::new(Dielectric { refraction_index });
+    }
+
+    if matches!(illumination_model, 3 | 5 | 8) {
+        let albedo: Vector3 = mtl_material.specular.map(to_vector).unwrap_or(diffuse);
+        let shininess: f64 = mtl_material.shininess.map(|shininess| shininess as f64).unwrap_or(0.0).max(0.0);
+        let fuzz: f64 = (2.0 / (shininess + 2.0)).sqrt();
+
+        return Arc::new(Metal { texture: Arc::new(SolidColor { color: albedo }), fuzz });
+    }
+
+    Arc::new(Diffuse { texture: Arc::new(SolidColor { color: diffuse }) })
+}
+
+fn parse_color(value: &str) -> Option<Vector3> {
+    let components: Vec<f64> = value.split_whitespace().map(|component| component.parse::<f64>()).collect::<Result<_, _>>().ok()?;
+
+    match components[..] {
+        [r, g, b] => Some(Vector3 { x: r, y: g, z: b }),
+        [gray] => Some(Vector3 { x: gray, y: gray, z: gray }),
+        _ => None,
+    }
+}
+
+fn to_vector(color: [f32; 3]) -> Vector3 {
+    Vector3 { x: color[0] as f64, y: color[1] as f64, z: color[2] as f64 }
+}
@@ -0,0 +1,194 @@
+use std::borrow::Cow;
+use std::error::Error;
+use std::fs;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use flate2::Compression;
+use flate2::write::ZlibEncoder;
+use image::{ImageBuffer, ImageFormat, ImageResult, Rgb, Rgb32FImage, RgbImage};
+use image::codecs::hdr::HdrEncoder;
+use image::codecs::jpeg::JpegEncoder;
+use tiff::encoder::{colortype, TiffEncoder, TiffValue};
+use tiff::tags::{Tag, Type};
+use crate::framebuffer::Framebuffer;
+use crate::color_space::ColorSpace;
+
+#[derive(Clone, Copy)]
+pub enum OutputFormat {
+    PNG,
+    JPEG,
+    TIFF,
+    BMP,
+    EXR,
+    HDR,
+    PFM,
+}
+
+impl OutputFormat {
+    pub fn is_high_dynamic_range(&self) -> bool {
+        matches!(self, OutputFormat::EXR | OutputFormat::HDR | OutputFormat::PFM)
+    }
+
+    // Low dynamic range formats that can embed the ICC profile of a color space
+    pub fn can_store_icc_profile(&self) -> bool {
+        matches!(self, OutputFormat::PNG | OutputFormat::JPEG | OutputFormat::TIFF)
+    }
+
+    pub fn from_path(path: &Path) -> Result<OutputFormat, String> {
+        let extension: String = path.extension()
+            .and_then(|extension| extension.to_str())
+            .map(|extension| extension.to_lowercase())
+            .unwrap_or_default();
+
+        match extension.as_str() {
+            "png" => Ok(OutputFormat::PNG),
+            "jpg" | "jpeg" => Ok(OutputFormat::JPEG),
+            "tif" | "tiff" => Ok(OutputFormat::TIFF),
+            "bmp" => Ok(OutputFormat::BMP),
+            "exr" => Ok(OutputFormat::EXR),
+            "hdr" => Ok(OutputFormat::HDR),
+            "pfm" => Ok(OutputFormat::PFM),
+            _ => Err(format!("Unsupported output format of '{}', expected one of .png, .jpg, .tiff, .bmp, .exr, .hdr or .pfm",
+                             path.display())),
+        }
+    }
+}
+
+// Low dynamic range formats are encoded into `color_space` and carry its ICC profile if it has one, high dynamic range
+// formats store the linear Rec. 709 values
+pub fn save(framebuffer: &Framebuffer, format: OutputFormat, path: &Path, color_space: ColorSpace, dither: Option<&mut fastrand::Rng>) -> Result<(), String> {
+    // write to a temporary file next to the output first, so that the output file always holds a complete image, even
+    // if the renderer is stopped while an intermediate image is being saved
+    let file_name: String = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+    let temporary_path: PathBuf = path.with_file_name(format!(".{}.tmp", file_name));
+
+    save_to(framebuffer, format, &temporary_path, color_space, dither)
+        .and_then(|_| fs::rename(&temporary_path, path).map_err(Box::from))
+        .map_err(|error| {
+            let _ = fs::remove_file(&temporary_path);
+            format!("Failed to save image to '{}': {}", path.display(), error)
+        })
+}
+
+fn save_to(framebuffer: &Framebuffer, format: OutputFormat, path: &Path, color_space: ColorSpace, dither: Option<&mut fastrand::Rng>) -> Result<(), Box<dyn Error>> {
+    let to_rgb_image = || to_rgb_image(framebuffer, color_space, dither);
+    let icc_profile: Option<Vec<u8>> = if format.is_high_dynamic_range() { None } else { color_space.icc_profile() };
+
+    match (format, icc_profile) {
+        (OutputFormat::PNG, Some(icc_profile)) => save_png_with_profile(&to_rgb_image(), path, &icc_profile)?,
+        (OutputFormat::JPEG, Some(icc_profile)) => save_jpeg_with_profile(&to_rgb_image(), path, &icc_profile)?,
+        (OutputFormat::TIFF, Some(icc_profile)) => save_tiff_with_profile(&to_rgb_image(), path, &icc_profile)?,
+        (_, Some(_)) => return Err(format!("the format can't store the color profile of {:?}, use png, jpg or tiff", color_space).into()),
+        (OutputFormat::PNG, None) => to_rgb_image().save_with_format(path, ImageFormat::Png)?,
+        (OutputFormat::JPEG, None) => to_rgb_image().save_with_format(path, ImageFormat::Jpeg)?,
+        (OutputFormat::TIFF, None) => to_rgb_image().save_with_format(path, ImageFormat::Tiff)?,
+        (OutputFormat::BMP, None) => to_rgb_image().save_with_format(path, ImageFormat::Bmp)?,
+        (OutputFormat::EXR, None) => to_float_image(framebuffer).save_with_format(path, ImageFormat::OpenExr)?,
+        (OutputFormat::HDR, None) => save_hdr(framebuffer, path)?,
+        (OutputFormat::PFM, None) => save_pfm(framebuffer, path)?,
+    }
+
+    Ok(())
+}
+
+// The profile is stored zlib compressed in an iCCP chunk in front of the image data
+fn save_png_with_profile(image: &RgbImage, path: &Path, icc_profile: &[u8]) -> Result<(), Box<dyn Error>> {
+    let mut chunk: Vec<u8> = b"ICC profile\0\0".to_vec();
+    let mut compressor = ZlibEncoder::new(&mut chunk, Compression::default());
+    compressor.write_all(icc_profile)?;
+    compressor.finish()?;
+
+    let mut encoder = png::Encoder::new(BufWriter::new(File::create(path)?), image.width(), image.height());
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    let mut writer = encoder.write_header()?;
+    writer.write_chunk(png::chunk::iCCP, &chunk)?;
+    writer.write_image_data(image.as_raw())?;
+    writer.finish()?;
+    Ok(())
+}
+
+// The profile is stored in an APP2 segment directly after the JFIF header
+fn save_jpeg_with_profile(image: &RgbImage, path: &Path, icc_profile: &[u8]) -> Result<(), Box<dyn Error>> {
+    let mut jpeg: Vec<u8> = Vec::new();
+    JpegEncoder::new(&mut jpeg).encode_image(image)?;
+
+    // a single segment holds up to 64 KB, which is plenty for a matrix profile
+    let mut segment: Vec<u8> = vec![0xFF, 0xE2];
+    segment.extend_from_slice(&((2 + 14 + icc_profile.len()) as u16).to_be_bytes());
+    segment.extend_from_slice(b"ICC_PROFILE\0");
+    segment.extend_from_slice(&[1, 1]); // sequence number and total number of segments
+    segment.extend_from_slice(icc_profile);
+
+    // skip the start of image marker and the JFIF APP0 segment
+    let mut position: usize = 2;
+    if jpeg[position..position + 2] == [0xFF, 0xE0] {
+        position += 2 + u16::from_be_bytes([jpeg[position + 2], jpeg[position + 3]]) as usize;
+    }
+    jpeg.splice(position..position, segment);
+
+    fs::write(path, jpeg)?;
+    Ok(())
+}
+
+fn save_tiff_with_profile(image: &RgbImage, path: &Path, icc_profile: &[u8]) -> Result<(), Box<dyn Error>> {
+    let mut encoder = TiffEncoder::new(BufWriter::new(File::create(path)?))?;
+    let mut tiff_image = encoder.new_image::<colortype::RGB8>(image.width(), image.height())?;
+    tiff_image.encoder().write_tag(Tag::Unknown(ICC_PROFILE_TAG), IccProfile(icc_profile))?;
+    tiff_image.write_data(image.as_raw())?;
+    Ok(())
+}
+
+const ICC_PROFILE_TAG: u16 = 34675;
+
+// TIFF stores ICC profiles as opaque bytes of the UNDEFINED field type
+struct IccProfile<'a>(&'a [u8]);
+
+impl TiffValue for IccProfile<'_> {
+    const BYTE_LEN: u8 = 1;
+    const FIELD_TYPE: Type = Type::UNDEFINED;
+
+    fn count(&self) -> usize {
+        self.0.len()
+    }
+
+    fn data(&self) -> Cow<'_, [u8]> {
+        Cow::Borrowed(self.0)
+    }
+}
+
+fn to_rgb_image(framebuffer: &Framebuffer, color_space: ColorSpace, mut dither: Option<&mut fastrand::Rng>) -> RgbImage {
+    ImageBuffer::from_fn(framebuffer.width, framebuffer.height, |x, y| color_space.quantize(framebuffer.get_pixel(x, y), dither.as_deref_mut()))
+}
+
+fn to_float_image(framebuffer: &Framebuffer) -> Rgb32FImage {
+    ImageBuffer::from_fn(framebuffer.width, framebuffer.height, |x, y| {
+        let color = framebuffer.get_pixel(x, y);
+        Rgb([color.x as f32, color.y as f32, color.z as f32])
+    })
+}
+
+fn save_hdr(framebuffer: &Framebuffer, path: &Path) -> ImageResult<()> {
+    let pixels: Vec<Rgb<f32>> = to_float_image(framebuffer).pixels().copied().collect();
+    HdrEncoder::new(BufWriter::new(File::create(path)?))
+        .encode(&pixels, framebuffer.width as usize, framebuffer.height as usize)
+}
+
+// Portable float map: a small text header followed by little endian floats, with rows stored from bottom to top
+fn save_pfm(framebuffer: &Framebuffer, path: &Path) -> std::io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write!(writer, "PF\n{} {}\n-1.0\n", framebuffer.width, framebuffer.height)?;
+
+    for y in (0..framebuffer.height).rev() {
+        for x in 0..framebuffer.width {
+            let color = framebuffer.get_pixel(x, y);
+            for component in [color.x, color.y, color.z] {
+                writer.write_all(&(component as f32).to_le_bytes())?;
+            }
+        }
+    }
+
+    writer.flush()
+}
@@ -1,59 +1,385 @@
-use image::Rgb;
-use crate::{Camera, HitRecord, IMAGE_HEIGHT, IMAGE_WIDTH, MAX_RAY_TRACE_DEPTH, Ray, ScatterInfo, Scene, Vector3};
+use std::ops::Range;
+use std::sync::{Arc, mpsc};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Instant;
+use crate::{Camera, Scene, Vector3};
+use crate::hit_record::HitRecord;
+use crate::ray::Ray;
+use crate::scatter_info::ScatterInfo;
+use crate::framebuffer::{AccumulationBuffer, PixelStatistics};
+use crate::tiles::{Tile, TileOrder, TileScheduler};
 
-pub fn render_row(y: u32, samples_per_pixel: u32, camera: &Camera, scene: &Scene) -> [Rgb<u8>; IMAGE_WIDTH as usize] {
-    let mut row: [Rgb<u8>; IMAGE_WIDTH as usize] = [Rgb([0, 0, 0]); IMAGE_WIDTH as usize];
+pub const DEFAULT_IMAGE_WIDTH: u32 = 1920;
+pub const DEFAULT_IMAGE_HEIGHT: u32 = 1080;
+pub const DEFAULT_SAMPLES_PER_PIXEL: u32 = 100;
+pub const DEFAULT_MIN_SAMPLES: u32 = 16;
+pub const DEFAULT_MAX_DEPTH: u32 = 50;
+pub const DEFAULT_RUSSIAN_ROULETTE_DEPTH: u32 = 5;
+pub const DEFAULT_TILE_SIZE: u32 = 32;
 
-    for x in 0..IMAGE_WIDTH {
-        let mut pixel_color: Vector3 = Vector3::zero();
+pub struct RenderSettings {
+    pub image_width: u32,
+    pub image_height: u32,
+    // number of samples every pixel should have once the render is finished
+    pub samples_per_pixel: u32,
+    // number of samples added to every pixel per pass over the image
+    pub samples_per_pass: u32,
+    // relative error below which a pixel with at least `min_samples` samples stops receiving samples
+    pub noise_threshold: Option<f64>,
+    pub min_samples: u32,
+    pub max_depth: u32,
+    pub russian_roulette_depth: u32,
+    pub seed: u64,
+    pub threads: u32,
+    pub tile_size: u32,
+    pub tile_order: TileOrder,
+    pub deadline: Option<Instant>,
+}
+
+impl Default for RenderSettings {
+    fn default() -> RenderSettings {
+        RenderSettings {
+            image_width: DEFAULT_IMAGE_WIDTH,
+            image_height: DEFAULT_IMAGE_HEIGHT,
+            samples_per_pixel: DEFAULT_SAMPLES_PER_PIXEL,
+            samples_per_pass: DEFAULT_SAMPLES_PER_PIXEL,
+            noise_threshold: None,
+            min_samples: DEFAULT_MIN_SAMPLES,
+            max_depth: DEFAULT_MAX_DEPTH,
+            russian_roulette_depth: DEFAULT_RUSSIAN_ROULETTE_DEPTH,
+            seed: 0,
+            threads: num_cpus::get() as u32,
+            tile_size: DEFAULT_TILE_SIZE,
+            tile_order: TileOrder::SPIRAL,
+            deadline: None,
+        }
+    }
+}
 
-        for _ in 0..samples_per_pixel {
-            let u: f64 = (x as f64 + fastrand::f64()) / (IMAGE_WIDTH as f64 - 1.0);
-            let v: f64 = (y as f64 + fastrand::f64()) / (IMAGE_HEIGHT as f64 - 1.0);
+// Passed to the progress callback of `Renderer::render` after every finished tile and at the end of every pass
+pub struct Progress {
+    pub pass: u32,
+    // estimated number of passes, resumed and adaptive renders may need fewer
+    pub pass_count: u32,
+    pub finished_tiles: usize,
+    pub total_tiles: usize,
+    pub is_pass_finished: bool,
+}
 
-            let ray: Ray = camera.get_ray(u, v);
-            pixel_color = pixel_color + ray_color(&scene, &ray, MAX_RAY_TRACE_DEPTH);
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum RenderStatus {
+    // every pixel has `samples_per_pixel` samples
+    FINISHED,
+    // every pixel has `samples_per_pixel` samples or is below the noise threshold
+    CONVERGED,
+    // the render was cancelled or reached its deadline before it was finished
+    STOPPED,
+}
+
+pub struct Renderer {
+    settings: RenderSettings,
+    camera: Camera,
+    scene: Scene,
+    cancelled: Arc<AtomicBool>,
+}
+
+// Work shared by the render threads during one pass: the tiles still to be rendered and the range of sample indices
+// every pixel receives
+struct RenderPass {
+    scheduler: TileScheduler,
+    pixel_samples: Vec<Range<u32>>,
+}
+
+// Sent by the render threads for every finished tile, and `None` once a thread has no more tiles of the current pass
+type TileMessage = Option<(Tile, Vec<PixelStatistics>)>;
+
+impl Renderer {
+    pub fn new(settings: RenderSettings, camera: Camera, scene: Scene) -> Result<Renderer, String> {
+        // pixel positions are divided by the size minus one when generating camera rays
+        if settings.image_width < 2 || settings.image_height < 2 {
+            return Err(format!("Invalid image size {}x{}, width and height must be at least 2 pixels", settings.image_width, settings.image_height));
         }
 
-        pixel_color = pixel_color / (samples_per_pixel as f64);
-        row[x as usize] = color_to_rgb(pixel_color);
+        if settings.tile_size == 0 {
+            return Err("Invalid tile size 0, tiles must be at least 1 pixel wide".to_string());
+        }
+
+        Ok(Renderer {
+            settings,
+            camera,
+            scene,
+            cancelled: Arc::new(AtomicBool::new(false)),
+        })
+    }
+
+    pub fn settings(&self) -> &RenderSettings {
+        &self.settings
+    }
+
+    // Flag that stops the render once it is set, e.g. from another thread or a signal handler. Tiles in progress are
+    // still finished, so that their samples are not lost.
+    pub fn cancellation_flag(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.cancelled)
+    }
+
+    // Whether the render was cancelled or has reached its deadline
+    pub fn is_stopped(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed) || self.settings.deadline.is_some_and(|deadline| Instant::now() >= deadline)
     }
 
-    row
+    // Renders passes over the image until every pixel has `samples_per_pixel` samples, has converged or the render is
+    // stopped. The samples are added to `accumulation_buffer`, which may already contain the samples of an earlier
+    // render with the same settings, and has to be of the image size of the settings. `on_progress` is called on the
+    // calling thread after every finished tile and pass.
+    pub fn render<F>(&self, accumulation_buffer: &mut AccumulationBuffer, mut on_progress: F) -> RenderStatus
+        where F: FnMut(&Progress, &AccumulationBuffer) {
+        let settings: &RenderSettings = &self.settings;
+        assert!(accumulation_buffer.width == settings.image_width && accumulation_buffer.height == settings.image_height,
+                "accumulation buffer of {}x{} pixels does not match the image size of {}x{} pixels",
+                accumulation_buffer.width, accumulation_buffer.height, settings.image_width, settings.image_height);
+
+        let samples_per_pass: u32 = settings.samples_per_pass.max(1);
+        let pass_samples = |accumulation_buffer: &AccumulationBuffer| -> Vec<Range<u32>> {
+            accumulation_buffer.pass_samples(samples_per_pass, settings.samples_per_pixel, settings.noise_threshold, settings.min_samples)
+        };
+
+        // every pixel continues with the samples following the ones it already has, so the number of passes is only
+        // an estimate for resumed or adaptive renders
+        let rendered_samples: u32 = accumulation_buffer.pixels.iter().map(|pixel| pixel.sample_count).min().unwrap_or(0);
+        let pass_count: u32 = settings.samples_per_pixel.saturating_sub(rendered_samples).div_ceil(samples_per_pass);
+
+        // the render threads are started once and wait for the next pass after finishing their tiles, they exit once
+        // the pass senders are dropped at the end of the scope
+        thread::scope(|scope| {
+            let (tiles_sender, tiles_receiver) = mpsc::channel::<TileMessage>();
+            let pass_senders: Vec<mpsc::Sender<Arc<RenderPass>>> = (0..settings.threads.max(1))
+                .map(|_| {
+                    let (pass_sender, pass_receiver) = mpsc::channel::<Arc<RenderPass>>();
+                    let thread_tiles_sender: mpsc::Sender<TileMessage> = tiles_sender.clone();
+                    scope.spawn(move || self.render_passes(pass_receiver, thread_tiles_sender));
+                    pass_sender
+                })
+                .collect();
+
+            let mut pass: u32 = 0;
+            loop {
+                let pixel_samples: Vec<Range<u32>> = pass_samples(accumulation_buffer);
+                if pixel_samples.iter().all(|samples| samples.is_empty()) {
+                    let is_finished: bool = accumulation_buffer.pixels.iter().all(|pixel| pixel.sample_count >= settings.samples_per_pixel);
+                    return if is_finished { RenderStatus::FINISHED } else { RenderStatus::CONVERGED };
+                }
+
+                let scheduler: TileScheduler = TileScheduler::new(settings.image_width, settings.image_height, settings.tile_size, settings.tile_order);
+                let tile_count: usize = scheduler.tile_count();
+                let render_pass: Arc<RenderPass> = Arc::new(RenderPass { scheduler, pixel_samples });
+                for pass_sender in &pass_senders {
+                    pass_sender.send(Arc::clone(&render_pass)).unwrap();
+                }
+
+                let mut progress: Progress = Progress {
+                    pass,
+                    pass_count: pass_count.max(pass + 1),
+                    finished_tiles: 0,
+                    total_tiles: pass_count.max(pass + 1) as usize * tile_count,
+                    is_pass_finished: false,
+                };
+                let mut finished_pass_tiles: usize = 0;
+                let mut working_threads: usize = pass_senders.len();
+
+                // once the render is stopped, no new tiles are started, but tiles in progress are still finished
+                while working_threads > 0 {
+                    match tiles_receiver.recv().unwrap() {
+                        Some((tile, statistics)) => {
+                            accumulation_buffer.add_tile(&tile, statistics);
+                            finished_pass_tiles += 1;
+
+                            progress.finished_tiles = pass as usize * tile_count + render_pass.scheduler.finished_tile_count();
+                            on_progress(&progress, accumulation_buffer);
+                        }
+                        None => working_threads -= 1,
+                    }
+                }
+
+                if self.is_stopped() && (finished_pass_tiles < tile_count || pass_samples(accumulation_buffer).iter().any(|samples| !samples.is_empty())) {
+                    return RenderStatus::STOPPED;
+                }
+
+                progress.is_pass_finished = true;
+                on_progress(&progress, accumulation_buffer);
+                pass += 1;
+            }
+        })
+    }
+
+    // Body of a render thread: renders tiles of every pass received from `passes` until all tiles of the pass have been
+    // handed out or the render is stopped, sending the statistics of each tile and `None` at the end of the pass
+    fn render_passes(&self, passes: mpsc::Receiver<Arc<RenderPass>>, tiles_sender: mpsc::Sender<TileMessage>) {
+        for render_pass in passes {
+            while !self.is_stopped() {
+                let Some(tile) = render_pass.scheduler.next() else { break };
+
+                let statistics: Vec<PixelStatistics> = render_tile(&tile, &self.settings, &self.camera, &self.scene, &render_pass.pixel_samples);
+                render_pass.scheduler.finish_tile();
+                tiles_sender.send(Some((tile, statistics))).unwrap();
+            }
+
+            tiles_sender.send(None).unwrap();
+        }
+    }
 }
 
-fn ray_color(scene: &Scene, ray: &Ray, depth: u32) -> Vector3 {
-    if depth <= 0 {
-        return Vector3::zero();
+// Renders the range of sample indices given in `pixel_samples` for the pixels of a tile and returns the statistics of
+// these samples for every pixel, row by row
+fn render_tile(tile: &Tile, settings: &RenderSettings, camera: &Camera, scene: &Scene, pixel_samples: &[Range<u32>]) -> Vec<PixelStatistics> {
+    let mut statistics: Vec<PixelStatistics> = Vec::with_capacity((tile.width * tile.height) as usize);
+
+    for y in tile.y..tile.y + tile.height {
+        for x in tile.x..tile.x + tile.width {
+            let mut pixel_statistics: PixelStatistics = PixelStatistics::new();
+
+            for sample in pixel_samples[(y * settings.image_width + x) as usize].clone() {
+                // every sample draws from its own random sequence, so the traced paths don't depend on which thread
+                // renders which tile, how the samples are split into passes or whether the render was resumed. The pass
+                // size still changes the order in which the samples are summed, and with it the last bits of the image.
+                let mut random: fastrand::Rng = fastrand::Rng::with_seed(sample_seed(settings.seed, x, y, sample));
+
+                let u: f64 = (x as f64 + random.f64()) / (settings.image_width as f64 - 1.0);
+                let v: f64 = (y as f64 + random.f64()) / (settings.image_height as f64 - 1.0);
+
+                let ray: Ray = camera.get_ray(u, v, &mut random);
+                pixel_statistics.add_sample(&ray_color(scene, ray, settings, &mut random));
+            }
+
+            statistics.push(pixel_statistics);
+        }
     }
 
-    let hit_record: HitRecord = scene.ray_hit_scene(&ray, 0.001, f64::INFINITY);
-    if hit_record.hit {
-        let scatter_info: ScatterInfo = hit_record.material.scatter(ray, &hit_record);
+    statistics
+}
+
+// Derives the seed of a single pixel sample by hashing the render seed with the pixel position and sample index
+fn sample_seed(seed: u64, x: u32, y: u32, sample: u32) -> u64 {
+    [x, y, sample].iter().fold(mix_bits(seed), |hash, value| mix_bits(hash ^ *value as u64))
+}
+
+// SplitMix64 finalizer, maps similar inputs to uncorrelated outputs
+fn mix_bits(value: u64) -> u64 {
+    let mut value: u64 = value.wrapping_add(0x9E3779B97F4A7C15);
+    value = (value ^ (value >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    value = (value ^ (value >> 27)).wrapping_mul(0x94D049BB133111EB);
+    value ^ (value >> 31)
+}
+
+fn ray_color(scene: &Scene, mut ray: Ray, settings: &RenderSettings, random: &mut fastrand::Rng) -> Vector3 {
+    let mut color: Vector3 = Vector3::zero();
+    let mut throughput: Vector3 = Vector3 { x: 1.0, y: 1.0, z: 1.0 };
+
+    // probability density with which the previous bounce chose the direction of `ray`, zero for camera rays and
+    // specular bounces
+    let mut scatter_pdf: f64 = 0.0;
 
-        if scatter_info.does_scatter {
-            return &scatter_info.attenuation * ray_color(scene, &scatter_info.scattered_ray, depth - 1);
+    for depth in 0..settings.max_depth {
+        let hit_record: HitRecord = scene.ray_hit_scene(&ray, 0.001, f64::INFINITY);
+        if !hit_record.hit {
+            let mut background_color: Vector3 = scene.background.get_color(&ray.direction);
+            let background_pdf: f64 = scene.background.pdf(&ray.direction);
+            if scatter_pdf > 0.0 && background_pdf > 0.0 {
+                background_color = background_color * power_heuristic(scatter_pdf, background_pdf);
+            }
+
+            color = color + &throughput * background_color;
+            break;
         }
 
-        return Vector3::zero();
+        let mut emitted: Vector3 = hit_record.material.emitted(&hit_record);
+        if scatter_pdf > 0.0 && !emitted.is_near_zero() {
+            emitted = emitted * power_heuristic(scatter_pdf, scene.light_pdf(&ray.origin, &ray.direction, hit_record.object));
+        }
+        color = color + &throughput * emitted;
+
+        let scatter_info: ScatterInfo = hit_record.material.scatter(&ray, &hit_record, random);
+        if !scatter_info.does_scatter {
+            break;
+        }
+
+        scatter_pdf = hit_record.material.pdf(&hit_record, &scatter_info.scattered_ray.direction);
+        if scatter_pdf > 0.0 {
+            color = color + &throughput * (sample_lights(scene, &hit_record, random) + sample_background(scene, &hit_record, random));
+        }
+
+        throughput = &throughput * scatter_info.attenuation;
+
+        // randomly terminate paths that carry little light, boosting the survivors to stay unbiased
+        if depth + 1 >= settings.russian_roulette_depth {
+            let survival_probability: f64 = throughput.x.max(throughput.y).max(throughput.z).min(1.0);
+            if survival_probability <= 0.0 || random.f64() >= survival_probability {
+                break;
+            }
+
+            throughput = throughput / survival_probability;
+        }
+
+        ray = scatter_info.scattered_ray;
     }
 
-    let unit_direction: Vector3 = ray.direction.normalized();
-    let t: f64 = 0.5 * (unit_direction.y + 1.0);
-    let color: Vector3 = Vector3 { x: 1.0, y: 1.0, z: 1.0 } * (1.0 - t) + Vector3 { x: 0.5, y: 0.7, z: 1.0 } * t;
     color
 }
 
-fn color_to_rgb(mut color: Vector3) -> Rgb<u8> {
-    color.x = color.x.sqrt();
-    color.y = color.y.sqrt();
-    color.z = color.z.sqrt();
+// Direct lighting from emissive objects, combined with the emission found by scattering through multiple importance
+// sampling
+fn sample_lights(scene: &Scene, hit_record: &HitRecord, random: &mut fastrand::Rng) -> Vector3 {
+    let (direction, light_pdf, light) = match scene.sample_light(&hit_record.point, random) {
+        Some(sample) => sample,
+        None => return Vector3::zero(),
+    };
+
+    let bsdf: Vector3 = hit_record.material.eval(hit_record, &direction);
+    if light_pdf <= 0.0 || bsdf.is_near_zero() {
+        return Vector3::zero();
+    }
+
+    let shadow_ray: Ray = Ray { origin: hit_record.point.clone(), direction };
+    // the probability density only holds for the sampled light, anything else in the way occludes it
+    let light_record: HitRecord = scene.ray_hit_scene(&shadow_ray, 0.001, f64::INFINITY);
+    if !light_record.hit || light_record.object != light {
+        return Vector3::zero();
+    }
+
+    let emitted: Vector3 = light_record.material.emitted(&light_record);
+    if emitted.is_near_zero() {
+        return Vector3::zero();
+    }
+
+    let scatter_pdf: f64 = hit_record.material.pdf(hit_record, &shadow_ray.direction);
+    let weight: f64 = power_heuristic(light_pdf, scatter_pdf) / light_pdf;
+    &bsdf * emitted * weight
+}
+
+// Direct lighting from an importance sampled background, combined with the light found by scattering through
+// multiple importance sampling
+fn sample_background(scene: &Scene, hit_record: &HitRecord, random: &mut fastrand::Rng) -> Vector3 {
+    let (direction, background_pdf) = match scene.background.sample(random) {
+        Some(sample) => sample,
+        None => return Vector3::zero(),
+    };
+
+    let bsdf: Vector3 = hit_record.material.eval(hit_record, &direction);
+    if background_pdf <= 0.0 || bsdf.is_near_zero() {
+        return Vector3::zero();
+    }
 
-    color.x = if color.x > 1.0 { 1.0 } else if color.x < 0.0 { 0.0 } else { color.x };
-    color.y = if color.y > 1.0 { 1.0 } else if color.y < 0.0 { 0.0 } else { color.y };
-    color.z = if color.z > 1.0 { 1.0 } else if color.z < 0.0 { 0.0 } else { color.z };
+    let shadow_ray: Ray = Ray { origin: hit_record.point.clone(), direction };
+    if scene.ray_hit_scene(&shadow_ray, 0.001, f64::INFINITY).hit {
+        return Vector3::zero();
+    }
 
-    color = color * 256.0;
+    let scatter_pdf: f64 = hit_record.material.pdf(hit_record, &shadow_ray.direction);
+    let weight: f64 = power_heuristic(background_pdf, scatter_pdf) / background_pdf;
+    &bsdf * scene.background.get_color(&shadow_ray.direction) * weight
+}
 
-    Rgb([color.x as u8, color.y as u8, color.z as u8])
+fn power_heuristic(pdf: f64, other_pdf: f64) -> f64 {
+    let squared: f64 = pdf * pdf;
+    squared / (squared + other_pdf * other_pdf)
 }
@@ -1,4 +1,5 @@
-use crate::{Ray, Vector3};
+use crate::Vector3;
+use crate::ray::Ray;
 
 pub struct ScatterInfo {
     pub does_scatter: bool,
@@ -1,40 +1,79 @@
-use crate::{HitRecord, Material, Ray, Sphere, Vector3};
+use std::sync::Arc;
+use crate::{Material, Vector3};
+use crate::hit_record::HitRecord;
+use crate::ray::Ray;
+use crate::sphere::Sphere;
+use crate::aabb::AABB;
+use crate::background::Background;
+use crate::bvh::BVH;
+use crate::distribution::Distribution1D;
+use crate::hittable::Hittable;
+use crate::material::{Dielectric, Diffuse, Metal};
 use crate::color_util::random_color;
-use crate::texture::Texture;
+use crate::texture::{Checkered, SolidColor};
 
+// The objects can't be changed after the scene is created, since the lights and the BVH are derived from them
 pub struct Scene {
-    pub spheres: Vec<Sphere>,
+    objects: Vec<Box<dyn Hittable>>,
+    pub background: Background,
+    // indices of the emissive objects and the distribution they are chosen by for direct lighting
+    lights: Vec<usize>,
+    light_distribution: Option<Distribution1D>,
+    // probability of choosing each object for direct lighting, zero for objects that are no lights
+    light_probabilities: Vec<f64>,
+    bvh: Option<BVH>,
 }
 
 impl Scene {
-    pub fn generate() -> Scene {
-        let mut spheres: Vec<Sphere> = Vec::new();
+    pub fn new(objects: Vec<Box<dyn Hittable>>) -> Scene {
+        let lights: Vec<usize> = (0..objects.len()).filter(|index| objects[*index].is_emissive()).collect();
+
+        // brighter and larger lights are chosen more often, lights without a power estimate only if all of them lack one
+        let mut light_probabilities: Vec<f64> = vec![0.0; objects.len()];
+        let light_distribution: Option<Distribution1D> = if lights.is_empty() {
+            None
+        } else {
+            let distribution: Distribution1D = Distribution1D::new(lights.iter().map(|index| objects[*index].emitted_power().max(0.0)).collect());
+            for (light, index) in lights.iter().enumerate() {
+                light_probabilities[*index] = distribution.probability(light);
+            }
+            Some(distribution)
+        };
+
+        Scene { objects, background: Background::sky(), lights, light_distribution, light_probabilities, bvh: None }
+    }
+
+    // Generates the random spheres scene, the layout and colors are determined by `seed`
+    pub fn generate(seed: u64) -> Scene {
+        let mut objects: Vec<Box<dyn Hittable>> = Vec::new();
+        let glass: Arc<dyn Material> = Arc::new(Dielectric { refraction_index: 1.5 });
 
         // GROUND
-        spheres.push(Sphere {
+        objects.push(Box::new(Sphere {
             center: Vector3 { x: 0.0, y: -1000.0, z: 1.0 },
             radius: 1000.0,
-            material: Material::DIFFUSE(Texture::CHECKERED(
-                Vector3 { x: 0.05, y: 0.05, z: 0.05 }, Vector3 { x: 0.95, y: 0.95, z: 0.95 })),
-        });
+            material: Arc::new(Diffuse {
+                texture: Arc::new(Checkered { odd: Vector3 { x: 0.05, y: 0.05, z: 0.05 }, even: Vector3 { x: 0.95, y: 0.95, z: 0.95 } }),
+            }),
+        }));
 
-        let mut random = fastrand::Rng::new();
+        let mut random = fastrand::Rng::with_seed(seed);
 
         // SMALL SPHERES
         for x in -11..11 {
             for z in -11..11 {
                 let material_random: f64 = random.f64();
 
-                let material: Material =
+                let material: Arc<dyn Material> =
                     if material_random < 0.8 {
-                        Material::DIFFUSE(Texture::SOLID(random_color()))
+                        Arc::new(Diffuse { texture: Arc::new(SolidColor { color: random_color(&mut random) }) })
                     } else if material_random < 0.95 {
-                        Material::METAL(Texture::SOLID(random_color()), 0.0)
+                        Arc::new(Metal { texture: Arc::new(SolidColor { color: random_color(&mut random) }), fuzz: 0.0 })
                     } else {
-                        Material::DIELECTRIC(1.5)
+                        Arc::clone(&glass)
                     };
 
-                spheres.push(Sphere {
+                objects.push(Box::new(Sphere {
                     center: Vector3 {
                         x: x as f64 + 0.9 * random.f64(),
                         y: 0.2,
@@ -42,39 +81,79 @@ impl Scene {
                     },
                     radius: 0.2,
                     material,
-                });
+                }));
             }
         }
 
         // BIG SPHERES
-        spheres.push(Sphere {
+        objects.push(Box::new(Sphere {
             center: Vector3 { x: 0.0, y: 1.0, z: 0.0 },
             radius: 1.0,
-            material: Material::DIELECTRIC(1.5),
-        });
+            material: glass,
+        }));
 
-        spheres.push(Sphere {
+        objects.push(Box::new(Sphere {
             center: Vector3 { x: -4.0, y: 1.0, z: 0.0 },
             radius: 1.0,
-            material: Material::DIFFUSE(Texture::SOLID(Vector3 { x: 0.6, y: 0.3, z: 0.1 })),
-        });
+            material: Arc::new(Diffuse { texture: Arc::new(SolidColor { color: Vector3 { x: 0.6, y: 0.3, z: 0.1 } }) }),
+        }));
 
-        spheres.push(Sphere {
+        objects.push(Box::new(Sphere {
             center: Vector3 { x: 4.0, y: 1.0, z: 0.0 },
             radius: 1.0,
-            material: Material::METAL(Texture::SOLID(Vector3 { x: 0.7, y: 0.6, z: 0.5 }), 0.0),
-        });
+            material: Arc::new(Metal { texture: Arc::new(SolidColor { color: Vector3 { x: 0.7, y: 0.6, z: 0.5 } }), fuzz: 0.0 }),
+        }));
+
+        Scene::new(objects)
+    }
+
+    pub fn objects(&self) -> &[Box<dyn Hittable>] {
+        &self.objects
+    }
+
+    pub fn build_bvh(&mut self) {
+        let bounding_boxes: Vec<AABB> = self.objects.iter().map(|object| object.bounding_box()).collect();
+        self.bvh = Some(BVH::build(&bounding_boxes));
+    }
+
+    // Samples a direction from `origin` towards a light chosen by its emitted power. Returns the direction, its
+    // probability density and the index of the light, the sample only counts if the light is what the direction hits.
+    pub fn sample_light(&self, origin: &Vector3, random: &mut fastrand::Rng) -> Option<(Vector3, f64, usize)> {
+        let light_distribution: &Distribution1D = self.light_distribution.as_ref()?;
+
+        let (_, _, light) = light_distribution.sample(random.f64());
+        let object: usize = self.lights[light];
+        let (direction, _) = self.objects[object].sample_direction(origin, random)?;
+        let pdf: f64 = self.light_pdf(origin, &direction, object);
+
+        Some((direction, pdf, object))
+    }
 
-        Scene { spheres }
+    // Probability density of `sample_light` choosing the light `object` and the direction towards it
+    pub fn light_pdf(&self, origin: &Vector3, direction: &Vector3, object: usize) -> f64 {
+        let probability: f64 = self.light_probabilities.get(object).copied().unwrap_or(0.0);
+        if probability <= 0.0 {
+            return 0.0;
+        }
+
+        probability * self.objects[object].direction_pdf(origin, direction)
+    }
+
+    pub fn ray_hit_scene(&self, ray: &Ray, t_min: f64, t_max: f64) -> HitRecord<'_> {
+        match &self.bvh {
+            Some(bvh) => bvh.ray_hit(&self.objects, ray, t_min, t_max),
+            None => self.ray_hit_all_objects(ray, t_min, t_max),
+        }
     }
 
-    pub fn ray_hit_scene(&self, ray: &Ray, t_min: f64, t_max: f64) -> HitRecord {
+    fn ray_hit_all_objects(&self, ray: &Ray, t_min: f64, t_max: f64) -> HitRecord<'_> {
         let mut current_record: HitRecord = HitRecord::no_hit();
         current_record.t = t_max;
 
-        for sphere in self.spheres.iter() {
-            let record: HitRecord = sphere.ray_hits_sphere(ray, t_min, current_record.t);
+        for (index, object) in self.objects.iter().enumerate() {
+            let mut record: HitRecord = object.ray_hit(ray, t_min, current_record.t);
             if record.hit {
+                record.object = index;
                 current_record = record;
             }
         }
@@ -0,0 +1,265 @@
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use serde::Deserialize;
+use toml::Spanned;
+use crate::{Camera, Material, Scene, Vector3};
+use crate::sphere::Sphere;
+use crate::background::{Background, EnvironmentMap};
+use crate::hittable::Hittable;
+use crate::image_texture::{ImageTexture, TextureCache, TextureEncoding, TextureFilter, TextureImage, WrapMode};
+use crate::material::{Dielectric, Diffuse, Emissive, Metal};
+use crate::mesh;
+use crate::texture::{Checkered, SolidColor, Texture};
+use crate::transform::Transform;
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SceneFile {
+    camera: Spanned<CameraDescription>,
+    background: Option<Spanned<BackgroundDescription>>,
+    #[serde(default)]
+    spheres: Vec<Spanned<SphereDescription>>,
+    #[serde(default)]
+    meshes: Vec<Spanned<MeshDescription>>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CameraDescription {
+    look_from: [f64; 3],
+    look_at: [f64; 3],
+    fov: Spanned<f64>,
+    #[serde(default)]
+    aperture: f64,
+    focus_distance: Option<f64>,
+}
+
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Deserialize)]
+#[serde(tag = "type", deny_unknown_fields)]
+enum BackgroundDescription {
+    BLACK,
+    SOLID { color: [f64; 3] },
+    GRADIENT { bottom: [f64; 3], top: [f64; 3] },
+    IMAGE {
+        path: String,
+        #[serde(default)]
+        rotation: f64,
+        #[serde(default = "default_intensity")]
+        intensity: f64,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SphereDescription {
+    center: [f64; 3],
+    radius: f64,
+    material: MaterialDescription,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MeshDescription {
+    path: String,
+    #[serde(default)]
+    translation: [f64; 3],
+    #[serde(default)]
+    rotation: [f64; 3],
+    #[serde(default = "default_scale")]
+    scale: [f64; 3],
+    material: Option<MaterialDescription>,
+}
+
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Deserialize)]
+#[serde(tag = "type", deny_unknown_fields)]
+enum MaterialDescription {
+    DIFFUSE { texture: TextureDescription },
+    METAL {
+        texture: TextureDescription,
+        #[serde(default)]
+        fuzz: f64,
+    },
+    DIELECTRIC { refraction_index: f64 },
+    EMISSIVE {
+        texture: TextureDescription,
+        #[serde(default = "default_intensity")]
+        intensity: f64,
+    },
+}
+
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Deserialize)]
+#[serde(tag = "type", deny_unknown_fields)]
+enum TextureDescription {
+    SOLID { color: [f64; 3] },
+    CHECKERED { odd: [f64; 3], even: [f64; 3] },
+    IMAGE {
+        path: String,
+        #[serde(default)]
+        filter: TextureFilter,
+        #[serde(default)]
+        wrap: WrapMode,
+        encoding: Option<TextureEncoding>,
+        #[serde(default = "default_texture_scale")]
+        scale: f64,
+    },
+}
+
+pub fn load(path: &str, aspect_ratio: f64) -> Result<(Camera, Scene), String> {
+    let source: String = fs::read_to_string(path)
+        .map_err(|error| format!("Failed to read scene file '{}': {}", path, error))?;
+
+    let base_directory: &Path = Path::new(path).parent().unwrap_or(Path::new(""));
+    parse(&source, base_directory, aspect_ratio).map_err(|error| format!("Invalid scene file '{}': {}", path, error))
+}
+
+fn parse(source: &str, base_directory: &Path, aspect_ratio: f64) -> Result<(Camera, Scene), String> {
+    let scene_file: SceneFile = toml::from_str(source).map_err(|error| error.to_string())?;
+
+    let camera_offset: usize = scene_file.camera.span().start;
+    let camera_description: CameraDescription = scene_file.camera.into_inner();
+    let fov: f64 = *camera_description.fov.get_ref();
+    if fov <= 0.0 || fov >= 180.0 {
+        return Err(error_at(source, camera_description.fov.span().start, "camera fov must be between 0 and 180 degrees"));
+    }
+
+    let look_from: Vector3 = to_vector(camera_description.look_from);
+    let look_at: Vector3 = to_vector(camera_description.look_at);
+
+    // the camera orientation is derived from the viewing direction and the world up direction
+    let forward: Vector3 = &look_at - &look_from;
+    if forward.is_near_zero() {
+        return Err(error_at(source, camera_offset, "camera look_from and look_at must be different points"));
+    }
+    if Vector3::up().cross(&forward.normalized()).is_near_zero() {
+        return Err(error_at(source, camera_offset, "camera must not look straight up or down"));
+    }
+    let focus_distance: f64 = camera_description.focus_distance.unwrap_or_else(|| (&look_at - &look_from).length());
+
+    let camera: Camera = Camera::new(look_from, look_at, fov, camera_description.aperture, focus_distance, aspect_ratio);
+
+    let mut textures: TextureCache = TextureCache::new();
+    let mut objects: Vec<Box<dyn Hittable>> = Vec::with_capacity(scene_file.spheres.len());
+    for sphere in scene_file.spheres {
+        let offset: usize = sphere.span().start;
+        let sphere: SphereDescription = sphere.into_inner();
+
+        if sphere.radius <= 0.0 {
+            return Err(error_at(source, offset, "sphere radius must be greater than 0"));
+        }
+
+        objects.push(Box::new(Sphere {
+            center: to_vector(sphere.center),
+            radius: sphere.radius,
+            material: to_material(sphere.material, base_directory, &mut textures).map_err(|message| error_at(source, offset, &message))?,
+        }));
+    }
+
+    for mesh in scene_file.meshes {
+        let offset: usize = mesh.span().start;
+        let mesh: MeshDescription = mesh.into_inner();
+
+        if mesh.scale.contains(&0.0) {
+            return Err(error_at(source, offset, "mesh scale must not be 0"));
+        }
+
+        let transform: Transform = Transform {
+            translation: to_vector(mesh.translation),
+            rotation: to_vector(mesh.rotation),
+            scale: to_vector(mesh.scale),
+        };
+
+        let material: Option<Arc<dyn Material>> = match mesh.material {
+            Some(material) => Some(to_material(material, base_directory, &mut textures).map_err(|message| error_at(source, offset, &message))?),
+            None => None,
+        };
+
+        let triangles: Vec<Box<dyn Hittable>> = mesh::load_obj(&base_directory.join(&mesh.path), &transform, material.as_ref())
+            .map_err(|message| error_at(source, offset, &message))?;
+        objects.extend(triangles);
+    }
+
+    let mut scene: Scene = Scene::new(objects);
+
+    if let Some(background) = scene_file.background {
+        let offset: usize = background.span().start;
+        scene.background = match background.into_inner() {
+            BackgroundDescription::BLACK => Background::SOLID(Vector3::zero()),
+            BackgroundDescription::SOLID { color } => Background::SOLID(to_vector(color)),
+            BackgroundDescription::GRADIENT { bottom, top } => Background::GRADIENT(to_vector(bottom), to_vector(top)),
+            BackgroundDescription::IMAGE { path, rotation, intensity } => {
+                let environment_map: EnvironmentMap = EnvironmentMap::load(&base_directory.join(path), rotation, intensity)
+                    .map_err(|message| error_at(source, offset, &message))?;
+                Background::IMAGE(environment_map)
+            }
+        };
+    }
+
+    Ok((camera, scene))
+}
+
+fn to_material(material: MaterialDescription, base_directory: &Path, textures: &mut TextureCache) -> Result<Arc<dyn Material>, String> {
+    match material {
+        MaterialDescription::DIFFUSE { texture } => Ok(Arc::new(Diffuse { texture: to_texture(texture, base_directory, textures)? })),
+        MaterialDescription::METAL { texture, fuzz } => {
+            if fuzz < 0.0 {
+                return Err(String::from("metal fuzz must not be negative"));
+            }
+
+            Ok(Arc::new(Metal { texture: to_texture(texture, base_directory, textures)?, fuzz }))
+        }
+        MaterialDescription::DIELECTRIC { refraction_index } => {
+            if refraction_index <= 0.0 {
+                return Err(String::from("dielectric refraction_index must be greater than 0"));
+            }
+
+            Ok(Arc::new(Dielectric { refraction_index }))
+        }
+        MaterialDescription::EMISSIVE { texture, intensity } => {
+            if intensity < 0.0 {
+                return Err(String::from("emissive intensity must not be negative"));
+            }
+
+            Ok(Arc::new(Emissive { texture: to_texture(texture, base_directory, textures)?, intensity }))
+        }
+    }
+}
+
+fn to_texture(texture: TextureDescription, base_directory: &Path, textures: &mut TextureCache) -> Result<Arc<dyn Texture>, String> {
+    match texture {
+        TextureDescription::SOLID { color } => Ok(Arc::new(SolidColor { color: to_vector(color) })),
+        TextureDescription::CHECKERED { odd, even } => Ok(Arc::new(Checkered { odd: to_vector(odd), even: to_vector(even) })),
+        TextureDescription::IMAGE { path, filter, wrap, encoding, scale } => {
+            if scale <= 0.0 {
+                return Err(String::from("image texture scale must be greater than 0"));
+            }
+
+            let image: Arc<TextureImage> = textures.load(&base_directory.join(path), encoding)?;
+            Ok(Arc::new(ImageTexture { image, filter, wrap, scale }))
+        }
+    }
+}
+
+fn default_intensity() -> f64 {
+    1.0
+}
+
+fn default_texture_scale() -> f64 {
+    1.0
+}
+
+fn default_scale() -> [f64; 3] {
+    [1.0, 1.0, 1.0]
+}
+
+fn to_vector(values: [f64; 3]) -> Vector3 {
+    Vector3 { x: values[0], y: values[1], z: values[2] }
+}
+
+fn error_at(source: &str, offset: usize, message: &str) -> String {
+    let line: usize = source[..offset].matches('\n').count() + 1;
+    format!("line {}: {}", line, message)
+}
@@ -1,15 +1,21 @@
-use crate::{Ray, Vector3};
+use std::f64::consts::PI;
+use std::sync::Arc;
+use crate::Vector3;
+use crate::ray::Ray;
+use crate::aabb::AABB;
+use crate::hittable::Hittable;
 use crate::hit_record::HitRecord;
 use crate::material::Material;
 
 pub struct Sphere {
     pub center: Vector3,
     pub radius: f64,
-    pub material: Material,
+    pub material: Arc<dyn Material>,
 }
 
-impl Sphere {
-    pub fn ray_hits_sphere(&self, ray: &Ray, t_min: f64, t_max: f64) -> HitRecord {
+impl Hittable for Sphere {
+    #[allow(clippy::needless_return)]
+    fn ray_hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> HitRecord<'_> {
         let oc: Vector3 = &ray.origin - &self.center;
         let a: f64 = ray.direction.length_squared();
         let half_b: f64 = oc.dot(&ray.direction);
@@ -33,6 +39,7 @@ impl Sphere {
         let point: Vector3 = ray.at(t);
         let normal: Vector3 = (&point - &self.center) / self.radius;
         let is_front_face: bool = ray.direction.dot(&normal) < 0.0;
+        let (u, v) = spherical_uv(&normal);
 
         return HitRecord {
             hit: true,
@@ -40,7 +47,75 @@ impl Sphere {
             point,
             normal: if is_front_face { normal } else { -&normal },
             is_front_face,
-            material: self.material.clone(),
+            u,
+            v,
+            material: self.material.as_ref(),
+            object: 0,
         };
     }
+
+    fn bounding_box(&self) -> AABB {
+        let radius: Vector3 = Vector3 { x: self.radius, y: self.radius, z: self.radius };
+
+        AABB {
+            min: &self.center - &radius,
+            max: &self.center + &radius,
+        }
+    }
+
+    fn is_emissive(&self) -> bool {
+        self.material.is_emissive()
+    }
+
+    fn emitted_power(&self) -> f64 {
+        4.0 * PI * self.radius * self.radius * self.material.emitted_luminance()
+    }
+
+    // Uniformly samples the cone of directions subtended by the sphere, or all directions if `origin` is inside of it
+    fn sample_direction(&self, origin: &Vector3, random: &mut fastrand::Rng) -> Option<(Vector3, f64)> {
+        let to_center: Vector3 = &self.center - origin;
+        let distance_squared: f64 = to_center.length_squared();
+        let radius_squared: f64 = self.radius * self.radius;
+
+        if distance_squared <= radius_squared {
+            return Some((Vector3::random_unit_vector(random), 1.0 / (4.0 * PI)));
+        }
+
+        let cos_theta_max: f64 = (1.0 - radius_squared / distance_squared).sqrt();
+        let cos_theta: f64 = 1.0 - random.f64() * (1.0 - cos_theta_max);
+        let sin_theta: f64 = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi: f64 = 2.0 * PI * random.f64();
+
+        let axis: Vector3 = to_center.normalized();
+        let (tangent, bitangent) = axis.orthonormal_basis();
+        let direction: Vector3 = &axis * cos_theta + &tangent * (sin_theta * phi.cos()) + &bitangent * (sin_theta * phi.sin());
+
+        Some((direction, 1.0 / (2.0 * PI * (1.0 - cos_theta_max))))
+    }
+
+    fn direction_pdf(&self, origin: &Vector3, direction: &Vector3) -> f64 {
+        let to_center: Vector3 = &self.center - origin;
+        let distance_squared: f64 = to_center.length_squared();
+        let radius_squared: f64 = self.radius * self.radius;
+
+        if distance_squared <= radius_squared {
+            return 1.0 / (4.0 * PI);
+        }
+
+        let cos_theta_max: f64 = (1.0 - radius_squared / distance_squared).sqrt();
+        let cos_theta: f64 = to_center.dot(direction) / (distance_squared.sqrt() * direction.length());
+        if cos_theta < cos_theta_max {
+            return 0.0;
+        }
+
+        1.0 / (2.0 * PI * (1.0 - cos_theta_max))
+    }
+}
+
+// Texture coordinates of a point on the unit sphere given by the outward normal. `u` runs around the vertical axis
+// starting at -x, `v` from the bottom (y = -1) to the top (y = 1) pole.
+fn spherical_uv(normal: &Vector3) -> (f64, f64) {
+    let u: f64 = ((-normal.z).atan2(normal.x) + PI) / (2.0 * PI);
+    let v: f64 = (-normal.y).clamp(-1.0, 1.0).acos() / PI;
+    (u, v)
 }
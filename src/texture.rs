@@ -1,20 +1,53 @@
 use crate::Vector3;
+use crate::hit_record::HitRecord;
 
-#[derive(Clone)]
-pub enum Texture {
-    SOLID(Vector3),
-    CHECKERED(Vector3, Vector3),
+// Everything a texture may look at to determine the color of a surface point
+pub struct ShadingContext<'a> {
+    pub point: &'a Vector3,
+    pub normal: &'a Vector3,
+    pub u: f64,
+    pub v: f64,
 }
 
-impl Texture {
-    pub fn get_color(&self, point: &Vector3) -> Vector3 {
-        return match self {
-            Texture::SOLID(albedo) => albedo.clone(),
-            Texture::CHECKERED(odd, even) => {
-                let size: f64 = 6.0;
-                let sin: f64 = (size * point.x).sin() * (size * point.y).sin() * (size * point.z).sin();
-                return (if sin < 0.0 { odd } else { even }).clone();
-            }
-        };
+impl<'a> ShadingContext<'a> {
+    pub fn from_hit_record(hit_record: &'a HitRecord) -> ShadingContext<'a> {
+        ShadingContext {
+            point: &hit_record.point,
+            normal: &hit_record.normal,
+            u: hit_record.u,
+            v: hit_record.v,
+        }
+    }
+}
+
+// Color of a surface depending on the shading context. Materials share their textures through an `Arc`, so textures
+// can be written outside of the library, either procedural ones or ones looking up an image by the UV coordinates.
+pub trait Texture: Send + Sync {
+    fn get_color(&self, context: &ShadingContext) -> Vector3;
+}
+
+pub struct SolidColor {
+    pub color: Vector3,
+}
+
+// Three-dimensional checker pattern in world space, independent of the texture coordinates
+pub struct Checkered {
+    pub odd: Vector3,
+    pub even: Vector3,
+}
+
+impl Texture for SolidColor {
+    fn get_color(&self, _context: &ShadingContext) -> Vector3 {
+        self.color.clone()
+    }
+}
+
+impl Texture for Checkered {
+    #[allow(clippy::needless_return)]
+    fn get_color(&self, context: &ShadingContext) -> Vector3 {
+        let size: f64 = 6.0;
+        let point: &Vector3 = context.point;
+        let sin: f64 = (size * point.x).sin() * (size * point.y).sin() * (size * point.z).sin();
+        return (if sin < 0.0 { &self.odd } else { &self.even }).clone();
     }
 }
@@ -0,0 +1,139 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use clap::ValueEnum;
+
+#[derive(Clone, Copy)]
+pub struct Tile {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum TileOrder {
+    /// Row by row from the top left corner
+    SCANLINE,
+    /// Spiraling outwards from the center of the image
+    SPIRAL,
+    /// Along a Hilbert curve, keeping consecutive tiles close to each other
+    HILBERT,
+}
+
+// Hands out tiles to render threads in a fixed order without locking
+pub struct TileScheduler {
+    tiles: Vec<Tile>,
+    next_tile: AtomicUsize,
+    // tiles are finished in a different order than they are handed out
+    finished_tiles: AtomicUsize,
+}
+
+impl TileScheduler {
+    pub fn new(image_width: u32, image_height: u32, tile_size: u32, order: TileOrder) -> TileScheduler {
+        let columns: u32 = image_width.div_ceil(tile_size);
+        let rows: u32 = image_height.div_ceil(tile_size);
+
+        let grid_positions: Vec<(u32, u32)> = match order {
+            TileOrder::SCANLINE => (0..rows).flat_map(|row| (0..columns).map(move |column| (column, row))).collect(),
+            TileOrder::SPIRAL => spiral_order(columns, rows),
+            TileOrder::HILBERT => hilbert_order(columns, rows),
+        };
+
+        let tiles: Vec<Tile> = grid_positions.into_iter()
+            .map(|(column, row)| {
+                let x: u32 = column * tile_size;
+                let y: u32 = row * tile_size;
+
+                Tile {
+                    x,
+                    y,
+                    width: tile_size.min(image_width - x),
+                    height: tile_size.min(image_height - y),
+                }
+            })
+            .collect();
+
+        TileScheduler { tiles, next_tile: AtomicUsize::new(0), finished_tiles: AtomicUsize::new(0) }
+    }
+
+    // Returns the tile that should be rendered next, or `None` if all tiles have been handed out
+    pub fn next(&self) -> Option<Tile> {
+        let index: usize = self.next_tile.fetch_add(1, Ordering::Relaxed);
+        self.tiles.get(index).copied()
+    }
+
+    // Called by the render threads once they have rendered a tile they got from `next`
+    pub fn finish_tile(&self) {
+        self.finished_tiles.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn finished_tile_count(&self) -> usize {
+        self.finished_tiles.load(Ordering::Relaxed)
+    }
+
+    pub fn tile_count(&self) -> usize {
+        self.tiles.len()
+    }
+}
+
+fn spiral_order(columns: u32, rows: u32) -> Vec<(u32, u32)> {
+    let tile_count: usize = (columns * rows) as usize;
+    let mut positions: Vec<(u32, u32)> = Vec::with_capacity(tile_count);
+
+    let mut column: i64 = (columns as i64 - 1) / 2;
+    let mut row: i64 = (rows as i64 - 1) / 2;
+    let directions: [(i64, i64); 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+    let mut direction: usize = 0;
+    let mut step_length: i64 = 1;
+
+    // walk the spiral with segment lengths 1, 1, 2, 2, 3, 3, ..., skipping positions outside of the image
+    while positions.len() < tile_count {
+        for _ in 0..2 {
+            for _ in 0..step_length {
+                if column >= 0 && row >= 0 && column < columns as i64 && row < rows as i64 {
+                    positions.push((column as u32, row as u32));
+                }
+
+                column += directions[direction].0;
+                row += directions[direction].1;
+            }
+
+            direction = (direction + 1) % 4;
+        }
+
+        step_length += 1;
+    }
+
+    positions
+}
+
+fn hilbert_order(columns: u32, rows: u32) -> Vec<(u32, u32)> {
+    let size: u32 = columns.max(rows).next_power_of_two();
+
+    let mut positions: Vec<(u32, u32)> = (0..rows).flat_map(|row| (0..columns).map(move |column| (column, row))).collect();
+    positions.sort_by_key(|(column, row)| hilbert_index(size, *column, *row));
+    positions
+}
+
+// Distance of the given position along a Hilbert curve covering a `size` x `size` grid
+fn hilbert_index(size: u32, mut x: u32, mut y: u32) -> u64 {
+    let mut index: u64 = 0;
+    let mut s: u32 = size / 2;
+
+    while s > 0 {
+        let rx: u32 = u32::from(x & s > 0);
+        let ry: u32 = u32::from(y & s > 0);
+        index += s as u64 * s as u64 * ((3 * rx) ^ ry) as u64;
+
+        if ry == 0 {
+            if rx == 1 {
+                x = size - 1 - x;
+                y = size - 1 - y;
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+
+        s /= 2;
+    }
+
+    index
+}
@@ -0,0 +1,76 @@
+use clap::ValueEnum;
+use crate::Vector3;
+use crate::color_util::luminance;
+use crate::framebuffer::Framebuffer;
+
+#[allow(non_camel_case_types)]
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum ToneMapping {
+    /// Clip values above 1
+    CLAMP,
+    /// Reinhard operator applied to the luminance
+    REINHARD,
+    /// Reinhard operator that maps the white point to 1 instead of infinity
+    EXTENDED_REINHARD,
+    /// Narkowicz' fit of the ACES filmic curve
+    ACES,
+    /// John Hable's filmic curve from Uncharted 2
+    UNCHARTED2,
+}
+
+impl ToneMapping {
+    // Applies the exposure (in stops) and the tone mapping operator, producing linear values in [0, 1]
+    pub fn apply(&self, framebuffer: &Framebuffer, exposure: f64, white_point: f64) -> Framebuffer {
+        let exposure_scale: f64 = 2.0_f64.powf(exposure);
+
+        Framebuffer {
+            width: framebuffer.width,
+            height: framebuffer.height,
+            pixels: framebuffer.pixels.iter()
+                .map(|color| self.map_color(&(color * exposure_scale), white_point))
+                .collect(),
+        }
+    }
+
+    fn map_color(&self, color: &Vector3, white_point: f64) -> Vector3 {
+        match self {
+            ToneMapping::CLAMP => color.clone(),
+            ToneMapping::REINHARD => scale_luminance(color, |l| l / (1.0 + l)),
+            ToneMapping::EXTENDED_REINHARD => scale_luminance(color, |l| l * (1.0 + l / (white_point * white_point)) / (1.0 + l)),
+            ToneMapping::ACES => map_channels(color, |x| (x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14)),
+            ToneMapping::UNCHARTED2 => {
+                const LINEAR_WHITE: f64 = 11.2;
+                const EXPOSURE_BIAS: f64 = 2.0;
+                map_channels(color, |x| hable(x * EXPOSURE_BIAS) / hable(LINEAR_WHITE))
+            }
+        }
+    }
+}
+
+fn scale_luminance<F: Fn(f64) -> f64>(color: &Vector3, curve: F) -> Vector3 {
+    let luminance: f64 = luminance(color);
+    if luminance <= 0.0 {
+        return Vector3::zero();
+    }
+
+    color * (curve(luminance) / luminance)
+}
+
+fn map_channels<F: Fn(f64) -> f64>(color: &Vector3, curve: F) -> Vector3 {
+    Vector3 {
+        x: curve(color.x.max(0.0)),
+        y: curve(color.y.max(0.0)),
+        z: curve(color.z.max(0.0)),
+    }
+}
+
+fn hable(x: f64) -> f64 {
+    const A: f64 = 0.15;
+    const B: f64 = 0.50;
+    const C: f64 = 0.10;
+    const D: f64 = 0.20;
+    const E: f64 = 0.02;
+    const F: f64 = 0.30;
+
+    ((x * (A * x + C * B) + D * E) / (x * (A * x + B) + D * F)) - E / F
+}
@@ -0,0 +1,45 @@
+use crate::Vector3;
+
+#[derive(Clone)]
+pub struct Transform {
+    pub translation: Vector3,
+    pub rotation: Vector3,
+    pub scale: Vector3,
+}
+
+impl Transform {
+    pub fn apply_to_point(&self, point: &Vector3) -> Vector3 {
+        &self.rotate(&(&self.scale * point.clone())) + &self.translation
+    }
+
+    // normals are transformed by the inverse transpose, which for scale followed by rotation means dividing by the scale
+    pub fn apply_to_normal(&self, normal: &Vector3) -> Vector3 {
+        let scaled: Vector3 = Vector3 { x: normal.x / self.scale.x, y: normal.y / self.scale.y, z: normal.z / self.scale.z };
+        self.rotate(&scaled).normalized()
+    }
+
+    // rotation angles are in degrees and applied around the x, y and then z axis
+    fn rotate(&self, vector: &Vector3) -> Vector3 {
+        let (sin_x, cos_x) = self.rotation.x.to_radians().sin_cos();
+        let (sin_y, cos_y) = self.rotation.y.to_radians().sin_cos();
+        let (sin_z, cos_z) = self.rotation.z.to_radians().sin_cos();
+
+        let x_rotated: Vector3 = Vector3 {
+            x: vector.x,
+            y: vector.y * cos_x - vector.z * sin_x,
+            z: vector.y * sin_x + vector.z * cos_x,
+        };
+
+        let y_rotated: Vector3 = Vector3 {
+            x: x_rotated.x * cos_y + x_rotated.z * sin_y,
+            y: x_rotated.y,
+            z: -x_rotated.x * sin_y + x_rotated.z * cos_y,
+        };
+
+        Vector3 {
+            x: y_rotated.x * cos_z - y_rotated.y * sin_z,
+            y: y_rotated.x * sin_z + y_rotated.y * cos_z,
+            z: y_rotated.z,
+        }
+    }
+}
@@ -0,0 +1,183 @@
+use std::sync::Arc;
+use crate::Vector3;
+use crate::hit_record::HitRecord;
+use crate::ray::Ray;
+use crate::aabb::AABB;
+use crate::hittable::Hittable;
+use crate::mesh::Mesh;
+
+pub struct Triangle {
+    pub mesh: Arc<Mesh>,
+    pub face: usize,
+}
+
+impl Triangle {
+    fn vertices(&self) -> [&Vector3; 3] {
+        let indices: &[usize; 3] = &self.mesh.indices[self.face];
+        [&self.mesh.positions[indices[0]], &self.mesh.positions[indices[1]], &self.mesh.positions[indices[2]]]
+    }
+
+    // Cross product of two edges, twice as long as the area and pointing along the geometric normal
+    fn cross(&self) -> Vector3 {
+        let [v0, v1, v2] = self.vertices();
+        (v1 - v0).cross(&(v2 - v0))
+    }
+
+    fn area(&self) -> f64 {
+        0.5 * self.cross().length()
+    }
+
+    // Converts the area density of sampling `point` into a solid angle density as seen from `origin`
+    fn solid_angle_pdf(&self, origin: &Vector3, point: &Vector3) -> f64 {
+        let cross: Vector3 = self.cross();
+        let area: f64 = 0.5 * cross.length();
+
+        let to_point: Vector3 = point - origin;
+        let distance_squared: f64 = to_point.length_squared();
+        let cos_light: f64 = cross.normalized().dot(&to_point.normalized()).abs();
+
+        if area <= 0.0 || cos_light <= 0.0 {
+            return 0.0;
+        }
+
+        distance_squared / (cos_light * area)
+    }
+}
+
+impl Hittable for Triangle {
+    // Watertight ray/triangle intersection (Woop, Benthin, Wald 2013): the vertices are transformed into a ray-aligned
+    // coordinate system, so rays through shared edges and vertices never slip between adjacent triangles.
+    fn ray_hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> HitRecord<'_> {
+        let [v0, v1, v2] = self.vertices();
+        let direction: &Vector3 = &ray.direction;
+
+        let abs_direction: [f64; 3] = [direction.x.abs(), direction.y.abs(), direction.z.abs()];
+        let kz: usize = if abs_direction[0] > abs_direction[1] {
+            if abs_direction[0] > abs_direction[2] { 0 } else { 2 }
+        } else if abs_direction[1] > abs_direction[2] { 1 } else { 2 };
+        let mut kx: usize = (kz + 1) % 3;
+        let mut ky: usize = (kx + 1) % 3;
+        if direction.axis(kz) < 0.0 {
+            std::mem::swap(&mut kx, &mut ky);
+        }
+
+        let shear_x: f64 = direction.axis(kx) / direction.axis(kz);
+        let shear_y: f64 = direction.axis(ky) / direction.axis(kz);
+        let shear_z: f64 = 1.0 / direction.axis(kz);
+
+        let a: Vector3 = v0 - &ray.origin;
+        let b: Vector3 = v1 - &ray.origin;
+        let c: Vector3 = v2 - &ray.origin;
+
+        let ax: f64 = a.axis(kx) - shear_x * a.axis(kz);
+        let ay: f64 = a.axis(ky) - shear_y * a.axis(kz);
+        let bx: f64 = b.axis(kx) - shear_x * b.axis(kz);
+        let by: f64 = b.axis(ky) - shear_y * b.axis(kz);
+        let cx: f64 = c.axis(kx) - shear_x * c.axis(kz);
+        let cy: f64 = c.axis(ky) - shear_y * c.axis(kz);
+
+        let u: f64 = cx * by - cy * bx;
+        let v: f64 = ax * cy - ay * cx;
+        let w: f64 = bx * ay - by * ax;
+
+        if (u < 0.0 || v < 0.0 || w < 0.0) && (u > 0.0 || v > 0.0 || w > 0.0) {
+            return HitRecord::no_hit();
+        }
+
+        let determinant: f64 = u + v + w;
+        if determinant == 0.0 {
+            return HitRecord::no_hit();
+        }
+
+        let scaled_t: f64 = u * shear_z * a.axis(kz) + v * shear_z * b.axis(kz) + w * shear_z * c.axis(kz);
+        let t: f64 = scaled_t / determinant;
+        if t < t_min || t > t_max {
+            return HitRecord::no_hit();
+        }
+
+        let barycentric: [f64; 3] = [u / determinant, v / determinant, w / determinant];
+        let indices: &[usize; 3] = &self.mesh.indices[self.face];
+
+        let geometric_normal: Vector3 = (v1 - v0).cross(&(v2 - v0));
+        let is_front_face: bool = direction.dot(&geometric_normal) < 0.0;
+
+        let normal: Vector3 = if self.mesh.normals.is_empty() {
+            geometric_normal.normalized()
+        } else {
+            let shading_normal: Vector3 = (&self.mesh.normals[indices[0]] * barycentric[0]
+                + &self.mesh.normals[indices[1]] * barycentric[1]
+                + &self.mesh.normals[indices[2]] * barycentric[2]).normalized();
+
+            // vertex normals disagreeing with the winding order would otherwise point into the surface after flipping
+            // by `is_front_face`, so that materials scatter into the object
+            if shading_normal.dot(&geometric_normal) < 0.0 { -&shading_normal } else { shading_normal }
+        };
+
+        let (texture_u, texture_v) = if self.mesh.texture_coordinates.is_empty() {
+            (barycentric[1], barycentric[2])
+        } else {
+            let uv: [&(f64, f64); 3] = [
+                &self.mesh.texture_coordinates[indices[0]],
+                &self.mesh.texture_coordinates[indices[1]],
+                &self.mesh.texture_coordinates[indices[2]],
+            ];
+            (
+                uv[0].0 * barycentric[0] + uv[1].0 * barycentric[1] + uv[2].0 * barycentric[2],
+                uv[0].1 * barycentric[0] + uv[1].1 * barycentric[1] + uv[2].1 * barycentric[2],
+            )
+        };
+
+        HitRecord {
+            hit: true,
+            t,
+            point: ray.at(t),
+            normal: if is_front_face { normal } else { -&normal },
+            is_front_face,
+            u: texture_u,
+            v: texture_v,
+            material: self.mesh.material.as_ref(),
+            object: 0,
+        }
+    }
+
+    fn bounding_box(&self) -> AABB {
+        let [v0, v1, v2] = self.vertices();
+        AABB::empty().surrounding_point(v0).surrounding_point(v1).surrounding_point(v2)
+    }
+
+    fn is_emissive(&self) -> bool {
+        self.mesh.material.is_emissive()
+    }
+
+    fn emitted_power(&self) -> f64 {
+        self.area() * self.mesh.material.emitted_luminance()
+    }
+
+    // Uniformly samples a point on the triangle's surface
+    fn sample_direction(&self, origin: &Vector3, random: &mut fastrand::Rng) -> Option<(Vector3, f64)> {
+        let [v0, v1, v2] = self.vertices();
+
+        let square_root: f64 = random.f64().sqrt();
+        let b0: f64 = 1.0 - square_root;
+        let b1: f64 = random.f64() * square_root;
+        let point: Vector3 = v0 * b0 + v1 * b1 + v2 * (1.0 - b0 - b1);
+
+        let pdf: f64 = self.solid_angle_pdf(origin, &point);
+        if pdf <= 0.0 {
+            return None;
+        }
+
+        // normalized, so that the minimum distance of the shadow ray does not grow with the distance of the light
+        Some(((&point - origin).normalized(), pdf))
+    }
+
+    fn direction_pdf(&self, origin: &Vector3, direction: &Vector3) -> f64 {
+        let ray: Ray = Ray { origin: origin.clone(), direction: direction.clone() };
+        let hit_record: HitRecord = self.ray_hit(&ray, 0.0, f64::INFINITY);
+        if !hit_record.hit {
+            return 0.0;
+        }
+
+        self.solid_angle_pdf(origin, &hit_record.point)
+    }
+}
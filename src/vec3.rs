@@ -1,7 +1,8 @@
 use std::fmt::{Display, Formatter};
 use std::ops::{Add, Div, Mul, Neg, Sub};
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Vector3 {
     pub x: f64,
     pub y: f64,
@@ -119,22 +120,46 @@ impl Vector3 {
         self / self.length()
     }
 
+    pub fn min(&self, other: &Vector3) -> Vector3 {
+        Vector3 { x: self.x.min(other.x), y: self.y.min(other.y), z: self.z.min(other.z) }
+    }
+
+    pub fn max(&self, other: &Vector3) -> Vector3 {
+        Vector3 { x: self.x.max(other.x), y: self.y.max(other.y), z: self.z.max(other.z) }
+    }
+
+    pub fn axis(&self, axis: usize) -> f64 {
+        match axis {
+            0 => self.x,
+            1 => self.y,
+            _ => self.z,
+        }
+    }
+
     pub fn is_near_zero(&self) -> bool {
         const EPSILON: f64 = 1e-8;
         self.x.abs() < EPSILON && self.y.abs() < EPSILON && self.z.abs() < EPSILON
     }
 
+    #[allow(clippy::needless_return)]
     pub const fn zero() -> Vector3 {
         return Vector3 { x: 0.0, y: 0.0, z: 0.0 };
     }
 
+    #[allow(clippy::needless_return)]
     pub const fn up() -> Vector3 {
         return Vector3 { x: 0.0, y: 1.0, z: 0.0 };
     }
 
-    pub fn random_unit_vector() -> Vector3 {
-        let mut random = fastrand::Rng::new();
+    // Returns two unit vectors that form an orthonormal basis together with this (normalized) vector
+    pub fn orthonormal_basis(&self) -> (Vector3, Vector3) {
+        let helper: Vector3 = if self.x.abs() > 0.9 { Vector3::up() } else { Vector3 { x: 1.0, y: 0.0, z: 0.0 } };
+        let tangent: Vector3 = self.cross(&helper).normalized();
+        let bitangent: Vector3 = self.cross(&tangent);
+        (tangent, bitangent)
+    }
 
+    pub fn random_unit_vector(random: &mut fastrand::Rng) -> Vector3 {
         loop {
             let vector: Vector3 = Vector3 {
                 x: random.f64() * 2.0 - 1.0,
@@ -0,0 +1,484 @@
+// Renders a scene built in code through the public library API
+
+use std::sync::Arc;
+use rust_ray_tracing::{Camera, Material, Renderer, RenderSettings, Scene, Texture, Vector3};
+use rust_ray_tracing::background::Background;
+use rust_ray_tracing::checkpoint::{self, CheckpointSettings};
+use rust_ray_tracing::color_space::ColorSpace;
+use rust_ray_tracing::framebuffer::{AccumulationBuffer, Framebuffer};
+use rust_ray_tracing::hit_record::HitRecord;
+use rust_ray_tracing::hittable::Hittable;
+use rust_ray_tracing::image_texture::{ImageTexture, TextureCache, TextureEncoding, TextureFilter, TextureImage, WrapMode};
+use rust_ray_tracing::material::{Diffuse, Emissive};
+use rust_ray_tracing::mesh::Mesh;
+use rust_ray_tracing::output::{self, OutputFormat};
+use rust_ray_tracing::ray::Ray;
+use rust_ray_tracing::scatter_info::ScatterInfo;
+use rust_ray_tracing::scene_file;
+use rust_ray_tracing::renderer::RenderStatus;
+use rust_ray_tracing::sphere::Sphere;
+use rust_ray_tracing::texture::{ShadingContext, SolidColor};
+
+// Material defined outside of the library, emits the same color in every direction and absorbs all light
+struct Glow {
+    color: Vector3,
+}
+
+impl Material for Glow {
+    fn scatter(&self, _ray: &Ray, _hit_record: &HitRecord, _random: &mut fastrand::Rng) -> ScatterInfo {
+        ScatterInfo::no_scatter()
+    }
+
+    fn emitted(&self, _hit_record: &HitRecord) -> Vector3 {
+        self.color.clone()
+    }
+}
+
+// Texture defined outside of the library, shows the texture coordinates as red and green
+struct TextureCoordinates;
+
+impl Texture for TextureCoordinates {
+    fn get_color(&self, context: &ShadingContext) -> Vector3 {
+        Vector3 { x: context.u, y: context.v, z: 0.0 }
+    }
+}
+
+fn sphere_scene() -> (Camera, Scene) {
+    sphere_scene_with_material(Arc::new(Diffuse { texture: Arc::new(SolidColor { color: Vector3 { x: 0.8, y: 0.2, z: 0.2 } }) }))
+}
+
+fn sphere_scene_with_material(material: Arc<dyn Material>) -> (Camera, Scene) {
+    let objects: Vec<Box<dyn Hittable>> = vec![
+        Box::new(Sphere { center: Vector3 { x: 0.0, y: 0.0, z: 0.0 }, radius: 1.0, material }),
+    ];
+
+    let camera: Camera = Camera::new(Vector3 { x: 0.0, y: 0.0, z: -5.0 }, Vector3::zero(), 40.0, 0.0, 5.0, 1.0);
+    (camera, Scene::new(objects))
+}
+
+#[test]
+fn renders_all_samples_in_passes() {
+    let (camera, scene) = sphere_scene();
+    let settings: RenderSettings = RenderSettings {
+        image_width: 16,
+        image_height: 16,
+        samples_per_pixel: 6,
+        samples_per_pass: 4,
+        threads: 2,
+        tile_size: 8,
+        ..RenderSettings::default()
+    };
+
+    let renderer: Renderer = Renderer::new(settings, camera, scene).unwrap();
+    let mut accumulation_buffer: AccumulationBuffer = AccumulationBuffer::new(16, 16);
+    let mut finished_passes: u32 = 0;
+    let mut finished_tiles: usize = 0;
+    let mut reported_tiles: Vec<usize> = Vec::new();
+
+    let status: RenderStatus = renderer.render(&mut accumulation_buffer, |progress, _| {
+        if progress.is_pass_finished {
+            finished_passes += 1;
+        } else {
+            finished_tiles += 1;
+            reported_tiles.push(progress.finished_tiles);
+        }
+    });
+
+    assert_eq!(status, RenderStatus::FINISHED);
+    assert_eq!(finished_passes, 2);
+    assert_eq!(finished_tiles, 8);
+    // the progress counts finished tiles over all passes, not the order they were handed out in
+    assert!(reported_tiles.windows(2).all(|pair| pair[0] <= pair[1]));
+    assert_eq!(reported_tiles[3], 4);
+    assert_eq!(reported_tiles[7], 8);
+    assert!(accumulation_buffer.pixels.iter().all(|pixel| pixel.sample_count == 6));
+
+    // the sphere covers the center of the image, the corners show the sky
+    let framebuffer: Framebuffer = accumulation_buffer.to_framebuffer();
+    let center: &Vector3 = framebuffer.get_pixel(8, 8);
+    let corner: &Vector3 = framebuffer.get_pixel(0, 0);
+    assert!(center.x > center.z);
+    assert!(corner.z > corner.x);
+}
+
+#[test]
+fn cancelled_render_stops_early() {
+    let (camera, scene) = sphere_scene();
+    let settings: RenderSettings = RenderSettings { image_width: 16, image_height: 16, ..RenderSettings::default() };
+
+    let renderer: Renderer = Renderer::new(settings, camera, scene).unwrap();
+    renderer.cancellation_flag().store(true, std::sync::atomic::Ordering::Relaxed);
+
+    let mut accumulation_buffer: AccumulationBuffer = AccumulationBuffer::new(16, 16);
+    let status: RenderStatus = renderer.render(&mut accumulation_buffer, |_, _| {});
+
+    assert_eq!(status, RenderStatus::STOPPED);
+    assert!(accumulation_buffer.pixels.iter().all(|pixel| pixel.sample_count == 0));
+}
+
+#[test]
+fn rejects_invalid_settings() {
+    for settings in [
+        RenderSettings { image_width: 1, image_height: 16, ..RenderSettings::default() },
+        RenderSettings { image_width: 16, image_height: 0, ..RenderSettings::default() },
+        RenderSettings { image_width: 16, image_height: 16, tile_size: 0, ..RenderSettings::default() },
+    ] {
+        let (camera, scene) = sphere_scene();
+        assert!(Renderer::new(settings, camera, scene).is_err());
+    }
+}
+
+#[test]
+#[should_panic(expected = "does not match the image size")]
+fn rejects_accumulation_buffer_of_other_size() {
+    let (camera, scene) = sphere_scene();
+    let settings: RenderSettings = RenderSettings { image_width: 16, image_height: 16, ..RenderSettings::default() };
+
+    let renderer: Renderer = Renderer::new(settings, camera, scene).unwrap();
+    let mut accumulation_buffer: AccumulationBuffer = AccumulationBuffer::new(16, 8);
+    renderer.render(&mut accumulation_buffer, |_, _| {});
+}
+
+// Renders the random spheres scene and returns the bits of every pixel, so that renders can be compared exactly
+fn render_random_spheres(threads: u32, has_bvh: bool) -> Vec<[u64; 3]> {
+    let mut scene: Scene = Scene::generate(0);
+    if has_bvh {
+        scene.build_bvh();
+    }
+
+    let camera: Camera = Camera::new(Vector3 { x: 12.0, y: 2.0, z: -3.0 }, Vector3::zero(), 25.0, 0.1, 10.0, 1.5);
+    let settings: RenderSettings = RenderSettings {
+        image_width: 24,
+        image_height: 16,
+        samples_per_pixel: 4,
+        seed: 7,
+        threads,
+        tile_size: 4,
+        ..RenderSettings::default()
+    };
+
+    let renderer: Renderer = Renderer::new(settings, camera, scene).unwrap();
+    let mut accumulation_buffer: AccumulationBuffer = AccumulationBuffer::new(24, 16);
+    renderer.render(&mut accumulation_buffer, |_, _| {});
+
+    let framebuffer: Framebuffer = accumulation_buffer.to_framebuffer();
+    (0..16).flat_map(|y| (0..24).map(move |x| (x, y)))
+        .map(|(x, y)| {
+            let color: &Vector3 = framebuffer.get_pixel(x, y);
+            [color.x.to_bits(), color.y.to_bits(), color.z.to_bits()]
+        })
+        .collect()
+}
+
+#[test]
+fn render_does_not_depend_on_thread_count() {
+    let single_thread: Vec<[u64; 3]> = render_random_spheres(1, true);
+    assert!(single_thread.iter().any(|pixel| *pixel != single_thread[0]));
+    assert!(render_random_spheres(4, true) == single_thread);
+}
+
+#[test]
+fn bvh_renders_the_same_as_testing_every_object() {
+    assert!(render_random_spheres(2, true) == render_random_spheres(2, false));
+}
+
+fn render_sphere_scene(accumulation_buffer: &mut AccumulationBuffer, samples_per_pixel: u32) {
+    let (camera, scene) = sphere_scene();
+    let settings: RenderSettings = RenderSettings {
+        image_width: 16,
+        image_height: 16,
+        samples_per_pixel,
+        // resuming continues with a new pass, so the straight render uses passes of the same size to sum the samples
+        // in the same order
+        samples_per_pass: 8,
+        seed: 3,
+        ..RenderSettings::default()
+    };
+
+    let renderer: Renderer = Renderer::new(settings, camera, scene).unwrap();
+    renderer.render(accumulation_buffer, |_, _| {});
+}
+
+fn checkpoint_settings(width: u32, height: u32) -> CheckpointSettings {
+    CheckpointSettings {
+        seed: 3,
+        width,
+        height,
+        max_depth: 50,
+        russian_roulette_depth: 5,
+        scene: None,
+        scene_hash: None,
+        background: None,
+        environment_rotation: None,
+        environment_intensity: None,
+    }
+}
+
+#[test]
+fn resumed_render_equals_straight_render() {
+    let directory: std::path::PathBuf = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("library");
+    std::fs::create_dir_all(&directory).unwrap();
+    let path: std::path::PathBuf = directory.join("resume.checkpoint");
+
+    let mut accumulation_buffer: AccumulationBuffer = AccumulationBuffer::new(16, 16);
+    render_sphere_scene(&mut accumulation_buffer, 8);
+    checkpoint::save(&path, &checkpoint_settings(16, 16), &accumulation_buffer).unwrap();
+
+    let (settings, mut resumed_buffer) = checkpoint::load(&path).unwrap();
+    assert_eq!((settings.seed, settings.width, settings.height), (3, 16, 16));
+    render_sphere_scene(&mut resumed_buffer, 16);
+
+    let mut straight_buffer: AccumulationBuffer = AccumulationBuffer::new(16, 16);
+    render_sphere_scene(&mut straight_buffer, 16);
+
+    let resumed: Framebuffer = resumed_buffer.to_framebuffer();
+    let straight: Framebuffer = straight_buffer.to_framebuffer();
+    for (resumed_pixel, straight_pixel) in resumed_buffer.pixels.iter().zip(&straight_buffer.pixels) {
+        assert_eq!(resumed_pixel.sample_count, straight_pixel.sample_count);
+    }
+    for y in 0..16 {
+        for x in 0..16 {
+            let (resumed_color, straight_color) = (resumed.get_pixel(x, y), straight.get_pixel(x, y));
+            assert_eq!([resumed_color.x, resumed_color.y, resumed_color.z], [straight_color.x, straight_color.y, straight_color.z]);
+        }
+    }
+}
+
+#[test]
+fn rejects_invalid_checkpoints() {
+    let directory: std::path::PathBuf = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("library");
+    std::fs::create_dir_all(&directory).unwrap();
+
+    let wrong_magic_path: std::path::PathBuf = directory.join("wrong_magic.checkpoint");
+    std::fs::write(&wrong_magic_path, b"NOTACKPTxxxxxxxxxxxxxxxx").unwrap();
+    let error: String = checkpoint::load(&wrong_magic_path).err().unwrap();
+    assert!(error.contains("not a checkpoint file"), "{}", error);
+
+    let wrong_size_path: std::path::PathBuf = directory.join("wrong_size.checkpoint");
+    checkpoint::save(&wrong_size_path, &checkpoint_settings(4, 4), &AccumulationBuffer::new(4, 2)).unwrap();
+    let error: String = checkpoint::load(&wrong_size_path).err().unwrap();
+    assert!(error.contains("image size does not match"), "{}", error);
+}
+
+#[test]
+fn renders_custom_material() {
+    let (camera, scene) = sphere_scene_with_material(Arc::new(Glow { color: Vector3 { x: 0.0, y: 2.0, z: 0.0 } }));
+    let settings: RenderSettings = RenderSettings { image_width: 16, image_height: 16, samples_per_pixel: 4, ..RenderSettings::default() };
+
+    let renderer: Renderer = Renderer::new(settings, camera, scene).unwrap();
+    let mut accumulation_buffer: AccumulationBuffer = AccumulationBuffer::new(16, 16);
+    renderer.render(&mut accumulation_buffer, |_, _| {});
+
+    let center: Vector3 = accumulation_buffer.to_framebuffer().get_pixel(8, 8).clone();
+    assert_eq!((center.x, center.y, center.z), (0.0, 2.0, 0.0));
+}
+
+#[test]
+fn renders_custom_texture() {
+    let material: Arc<dyn Material> = Arc::new(Emissive { texture: Arc::new(TextureCoordinates), intensity: 1.0 });
+    let (camera, scene) = sphere_scene_with_material(material);
+    let settings: RenderSettings = RenderSettings { image_width: 16, image_height: 16, samples_per_pixel: 4, ..RenderSettings::default() };
+
+    let renderer: Renderer = Renderer::new(settings, camera, scene).unwrap();
+    let mut accumulation_buffer: AccumulationBuffer = AccumulationBuffer::new(16, 16);
+    renderer.render(&mut accumulation_buffer, |_, _| {});
+
+    // the camera looks at the sphere along +z, the center of the image shows the point facing -z at u = 0.75, v = 0.5,
+    // `v` grows towards the top pole
+    let framebuffer: Framebuffer = accumulation_buffer.to_framebuffer();
+    let center: &Vector3 = framebuffer.get_pixel(8, 8);
+    assert!((center.x - 0.75).abs() < 0.1 && (center.y - 0.5).abs() < 0.1);
+    assert!(framebuffer.get_pixel(8, 5).y > framebuffer.get_pixel(8, 11).y);
+}
+
+// Samples a texture at the given UV coordinates
+fn texture_color(texture: &dyn Texture, u: f64, v: f64) -> Vector3 {
+    let point: Vector3 = Vector3::zero();
+    texture.get_color(&ShadingContext { point: &point, normal: &point, u, v })
+}
+
+#[test]
+fn image_texture_filters_and_wraps() {
+    // a 2 x 1 image, black on the left and white on the right
+    let image: Arc<TextureImage> = Arc::new(TextureImage::from_pixels(2, 1, vec![Vector3::zero(), Vector3 { x: 1.0, y: 1.0, z: 1.0 }]));
+    let texture = |filter: TextureFilter, wrap: WrapMode| -> ImageTexture {
+        ImageTexture { image: Arc::clone(&image), filter, wrap, scale: 1.0 }
+    };
+
+    let nearest: ImageTexture = texture(TextureFilter::NEAREST, WrapMode::REPEAT);
+    assert_eq!(texture_color(&nearest, 0.25, 0.5).x, 0.0);
+    assert_eq!(texture_color(&nearest, 0.75, 0.5).x, 1.0);
+    assert_eq!(texture_color(&nearest, 1.25, 0.5).x, 0.0);
+
+    // halfway between the two texel centers
+    let bilinear: ImageTexture = texture(TextureFilter::BILINEAR, WrapMode::CLAMP);
+    assert_eq!(texture_color(&bilinear, 0.5, 0.5).x, 0.5);
+    assert_eq!(texture_color(&bilinear, 1.5, 0.5).x, 1.0);
+
+    let clamp: ImageTexture = texture(TextureFilter::NEAREST, WrapMode::CLAMP);
+    assert_eq!(texture_color(&clamp, 1.25, 0.5).x, 1.0);
+    assert_eq!(texture_color(&clamp, -0.75, 0.5).x, 0.0);
+
+    let mirror: ImageTexture = texture(TextureFilter::NEAREST, WrapMode::MIRROR);
+    assert_eq!(texture_color(&mirror, 1.25, 0.5).x, 1.0);
+    assert_eq!(texture_color(&mirror, 1.75, 0.5).x, 0.0);
+    assert_eq!(texture_color(&mirror, -0.25, 0.5).x, 0.0);
+}
+
+#[test]
+fn texture_cache_loads_files_once() {
+    let directory: &std::path::Path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"));
+    let mut cache: TextureCache = TextureCache::new();
+
+    let srgb: Arc<TextureImage> = cache.load(&directory.join("scenes/textures/grid.png"), None).unwrap();
+    let same_file: Arc<TextureImage> = cache.load(&directory.join("scenes/../scenes/textures/grid.png"), None).unwrap();
+    assert!(Arc::ptr_eq(&srgb, &same_file));
+
+    // PNG files are sRGB encoded by default
+    let explicit_srgb: Arc<TextureImage> = cache.load(&directory.join("scenes/textures/grid.png"), Some(TextureEncoding::SRGB)).unwrap();
+    assert!(Arc::ptr_eq(&srgb, &explicit_srgb));
+
+    // the same file decoded without the sRGB transfer function is a different image with brighter mid tones
+    let linear: Arc<TextureImage> = cache.load(&directory.join("scenes/textures/grid.png"), Some(TextureEncoding::LINEAR)).unwrap();
+    assert!(!Arc::ptr_eq(&srgb, &linear));
+
+    let texture = |image: &Arc<TextureImage>| -> Vector3 {
+        let texture: ImageTexture = ImageTexture { image: Arc::clone(image), filter: TextureFilter::NEAREST, wrap: WrapMode::REPEAT, scale: 1.0 };
+        texture_color(&texture, 0.01, 0.5)
+    };
+    assert!(texture(&linear).y > texture(&srgb).y);
+
+    assert!(cache.load(&directory.join("scenes/textures/missing.png"), None).is_err());
+}
+
+#[test]
+fn renders_empty_scene_with_bvh() {
+    let mut scene: Scene = Scene::new(Vec::new());
+    scene.build_bvh();
+
+    let camera: Camera = Camera::new(Vector3 { x: 0.0, y: 0.0, z: -5.0 }, Vector3::zero(), 40.0, 0.0, 5.0, 1.0);
+    let settings: RenderSettings = RenderSettings { image_width: 8, image_height: 8, samples_per_pixel: 2, ..RenderSettings::default() };
+
+    let renderer: Renderer = Renderer::new(settings, camera, scene).unwrap();
+    let mut accumulation_buffer: AccumulationBuffer = AccumulationBuffer::new(8, 8);
+    renderer.render(&mut accumulation_buffer, |_, _| {});
+
+    // only the sky is visible, which is bluer at the top
+    let framebuffer: Framebuffer = accumulation_buffer.to_framebuffer();
+    assert!(framebuffer.get_pixel(4, 0).x < framebuffer.get_pixel(4, 7).x);
+}
+
+#[test]
+fn shading_normal_faces_the_ray() {
+    // counterclockwise seen from -z, so the geometric normal points towards -z, but the vertex normals point towards +z
+    let mesh: Mesh = Mesh {
+        positions: vec![Vector3 { x: -1.0, y: -1.0, z: 0.0 }, Vector3 { x: 1.0, y: -1.0, z: 0.0 }, Vector3 { x: 0.0, y: 1.0, z: 0.0 }],
+        normals: vec![Vector3 { x: 0.0, y: 0.0, z: 1.0 }; 3],
+        texture_coordinates: Vec::new(),
+        indices: vec![[0, 2, 1]],
+        material: Arc::new(Diffuse { texture: Arc::new(SolidColor { color: Vector3::zero() }) }),
+    };
+    let scene: Scene = Scene::new(mesh.into_triangles());
+
+    for z in [-1.0, 1.0] {
+        let ray: Ray = Ray { origin: Vector3 { x: 0.0, y: 0.0, z: 5.0 * z }, direction: Vector3 { x: 0.0, y: 0.0, z: -z } };
+        let hit_record: HitRecord = scene.ray_hit_scene(&ray, 0.001, f64::INFINITY);
+
+        assert!(hit_record.hit);
+        assert!(hit_record.normal.dot(&ray.direction) < 0.0);
+    }
+}
+
+fn horizontal_triangle(y: f64, is_facing_down: bool, material: Arc<dyn Material>) -> Vec<Box<dyn Hittable>> {
+    let mesh: Mesh = Mesh {
+        positions: vec![Vector3 { x: -10.0, y, z: -10.0 }, Vector3 { x: 10.0, y, z: -10.0 }, Vector3 { x: 0.0, y, z: 10.0 }],
+        normals: Vec::new(),
+        texture_coordinates: Vec::new(),
+        indices: vec![if is_facing_down { [0, 1, 2] } else { [0, 2, 1] }],
+        material,
+    };
+    mesh.into_triangles()
+}
+
+// Renders a floor lit by a distant triangle light, seen from a camera between the floor and an optional blocker
+fn render_under_distant_light(has_blocker: bool) -> Framebuffer {
+    let mut objects: Vec<Box<dyn Hittable>> = Vec::new();
+    objects.extend(horizontal_triangle(100.0, true, Arc::new(Emissive { texture: Arc::new(SolidColor { color: Vector3 { x: 1.0, y: 1.0, z: 1.0 } }), intensity: 100.0 })));
+    objects.extend(horizontal_triangle(0.0, false, Arc::new(Diffuse { texture: Arc::new(SolidColor { color: Vector3 { x: 0.8, y: 0.8, z: 0.8 } }) })));
+    if has_blocker {
+        objects.extend(horizontal_triangle(0.05, false, Arc::new(Diffuse { texture: Arc::new(SolidColor { color: Vector3::zero() }) })));
+    }
+
+    let mut scene: Scene = Scene::new(objects);
+    scene.background = Background::SOLID(Vector3::zero());
+
+    let camera: Camera = Camera::new(Vector3 { x: 0.0, y: 0.03, z: 0.0 }, Vector3 { x: 0.0, y: 0.0, z: 0.05 }, 40.0, 0.0, 1.0, 1.0);
+    let settings: RenderSettings = RenderSettings { image_width: 8, image_height: 8, samples_per_pixel: 4, ..RenderSettings::default() };
+
+    let renderer: Renderer = Renderer::new(settings, camera, scene).unwrap();
+    let mut accumulation_buffer: AccumulationBuffer = AccumulationBuffer::new(8, 8);
+    renderer.render(&mut accumulation_buffer, |_, _| {});
+    accumulation_buffer.to_framebuffer()
+}
+
+#[test]
+fn blocker_near_surface_shadows_distant_light() {
+    let lit: Framebuffer = render_under_distant_light(false);
+    assert!(lit.get_pixel(4, 6).x > 0.1);
+
+    // the blocker is much closer to the floor than the light, but still casts a shadow on all of it
+    let shadowed: Framebuffer = render_under_distant_light(true);
+    for y in 0..8 {
+        for x in 0..8 {
+            assert!(shadowed.get_pixel(x, y).is_near_zero());
+        }
+    }
+}
+
+#[test]
+fn tagged_output_embeds_icc_profile() {
+    let framebuffer: Framebuffer = AccumulationBuffer::new(4, 4).to_framebuffer();
+    let directory: std::path::PathBuf = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("library");
+    std::fs::create_dir_all(&directory).unwrap();
+    assert_ne!(ColorSpace::DISPLAY_P3.icc_profile(), ColorSpace::LINEAR_REC709.icc_profile());
+
+    for color_space in [ColorSpace::DISPLAY_P3, ColorSpace::LINEAR_REC709] {
+        for (format, extension) in [(OutputFormat::PNG, "png"), (OutputFormat::JPEG, "jpg")] {
+            let path: std::path::PathBuf = directory.join(format!("{:?}.{}", color_space, extension));
+            output::save(&framebuffer, format, &path, color_space, None).unwrap();
+
+            let mut decoder = image::io::Reader::open(&path).unwrap().into_decoder().unwrap();
+            assert_eq!(image::ImageDecoder::icc_profile(&mut decoder).unwrap(), color_space.icc_profile(), "{}", extension);
+        }
+
+        // the TIFF decoder of the image crate only finds profiles stored as BYTE instead of UNDEFINED, read the tag directly
+        let tiff_path: std::path::PathBuf = directory.join(format!("{:?}.tiff", color_space));
+        output::save(&framebuffer, OutputFormat::TIFF, &tiff_path, color_space, None).unwrap();
+        let mut decoder = tiff::decoder::Decoder::new(std::fs::File::open(&tiff_path).unwrap()).unwrap();
+        assert_eq!(decoder.get_tag_u8_vec(tiff::tags::Tag::Unknown(34675)).ok(), color_space.icc_profile());
+
+        let bmp_path: std::path::PathBuf = directory.join(format!("{:?}.bmp", color_space));
+        assert!(output::save(&framebuffer, OutputFormat::BMP, &bmp_path, color_space, None).is_err());
+    }
+}
+
+#[test]
+fn rejects_degenerate_camera_in_scene_file() {
+    let directory: std::path::PathBuf = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("library");
+    std::fs::create_dir_all(&directory).unwrap();
+
+    for (name, look_at, message) in [
+        ("same_point", "[0.0, 1.0, 0.0]", "look_from and look_at must be different"),
+        ("straight_down", "[0.0, 0.0, 0.0]", "must not look straight up or down"),
+    ] {
+        let path: std::path::PathBuf = directory.join(format!("camera_{}.toml", name));
+        std::fs::write(&path, format!("# degenerate camera\n\n[camera]\nlook_from = [0.0, 1.0, 0.0]\nlook_at = {}\nfov = 40.0\n", look_at)).unwrap();
+
+        let error: String = match scene_file::load(path.to_str().unwrap(), 1.0) {
+            Ok(_) => panic!("camera looking at {} was accepted", look_at),
+            Err(error) => error,
+        };
+        assert!(error.contains("line 3: "), "{}", error);
+        assert!(error.contains(message), "{}", error);
+    }
+}
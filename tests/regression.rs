@@ -0,0 +1,131 @@
+// Renders small versions of the canonical scenes with a fixed seed and compares them against the reference images in
+// `tests/references`. After an intended change of the rendered look, regenerate the references with
+// `UPDATE_REFERENCES=1 cargo test --test regression` and check the new images before committing them.
+
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use image::{Rgb, RgbImage};
+
+const SEED: &str = "1";
+const WIDTH: &str = "96";
+const HEIGHT: &str = "64";
+const SAMPLES_PER_PIXEL: &str = "16";
+
+// Maximum root mean square error between the rendered and the reference image, with channels in [0, 1]
+const MAX_RMSE: f64 = 0.01;
+
+// Maximum fraction of pixels whose channels may differ by more than `PIXEL_THRESHOLD`
+const MAX_DIFFERENT_PIXELS: f64 = 0.01;
+const PIXEL_THRESHOLD: f64 = 0.1;
+
+#[test]
+fn random_spheres() {
+    assert_matches_reference("random_spheres", &[]);
+}
+
+#[test]
+fn three_spheres() {
+    assert_matches_reference("three_spheres", &["--scene", "scenes/three_spheres.toml"]);
+}
+
+#[test]
+fn mesh() {
+    assert_matches_reference("mesh", &["--scene", "scenes/mesh.toml"]);
+}
+
+#[test]
+fn cornell_box() {
+    assert_matches_reference("cornell_box", &["--scene", "scenes/cornell_box.toml"]);
+}
+
+#[test]
+fn textured_spheres() {
+    assert_matches_reference("textured_spheres", &["--scene", "scenes/textured_spheres.toml"]);
+}
+
+#[test]
+fn gradient_background() {
+    assert_matches_reference("gradient_background", &[
+        "--scene", "scenes/three_spheres.toml", "--background", "gradient:0.9,0.6,0.3:0.1,0.2,0.6",
+    ]);
+}
+
+fn assert_matches_reference(name: &str, arguments: &[&str]) {
+    let rendered_path: PathBuf = output_directory().join(format!("{}.png", name));
+    render(arguments, &rendered_path);
+
+    let reference_path: PathBuf = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/references").join(format!("{}.png", name));
+    if env::var_os("UPDATE_REFERENCES").is_some() {
+        std::fs::copy(&rendered_path, &reference_path).expect("failed to update reference image");
+        return;
+    }
+
+    let rendered: RgbImage = load(&rendered_path);
+    let reference: RgbImage = load(&reference_path);
+    assert_eq!(rendered.dimensions(), reference.dimensions(), "{}: image size differs from the reference", name);
+
+    let comparison: Comparison = compare(&rendered, &reference);
+    if comparison.rmse > MAX_RMSE || comparison.different_pixels > MAX_DIFFERENT_PIXELS {
+        let difference_path: PathBuf = output_directory().join(format!("{}_difference.png", name));
+        comparison.difference.save(&difference_path).expect("failed to save difference image");
+
+        panic!("{}: rendered image {} differs from the reference {} (RMSE {:.4}, {:.2}% of pixels differ), see {}",
+               name, rendered_path.display(), reference_path.display(), comparison.rmse,
+               comparison.different_pixels * 100.0, difference_path.display());
+    }
+}
+
+fn render(arguments: &[&str], output: &Path) {
+    let result = Command::new(env!("CARGO_BIN_EXE_rust-ray-tracing"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .args(["--seed", SEED, "--width", WIDTH, "--height", HEIGHT, "-s", SAMPLES_PER_PIXEL, "--overwrite", "-o"])
+        .arg(output)
+        .args(arguments)
+        .output()
+        .expect("failed to run the renderer");
+
+    assert!(result.status.success(), "renderer failed: {}", String::from_utf8_lossy(&result.stderr));
+}
+
+struct Comparison {
+    rmse: f64,
+    different_pixels: f64,
+    difference: RgbImage,
+}
+
+// Compares two images channel by channel. The difference image shows the absolute error amplified by a factor of 4.
+fn compare(rendered: &RgbImage, reference: &RgbImage) -> Comparison {
+    let mut squared_error_sum: f64 = 0.0;
+    let mut different_pixel_count: usize = 0;
+    let mut difference: RgbImage = RgbImage::new(rendered.width(), rendered.height());
+
+    for ((rendered_pixel, reference_pixel), difference_pixel) in rendered.pixels().zip(reference.pixels()).zip(difference.pixels_mut()) {
+        let errors: [f64; 3] = [0, 1, 2].map(|channel| (rendered_pixel[channel] as f64 - reference_pixel[channel] as f64).abs() / 255.0);
+
+        squared_error_sum += errors.iter().map(|error| error * error).sum::<f64>();
+        if errors.iter().any(|error| *error > PIXEL_THRESHOLD) {
+            different_pixel_count += 1;
+        }
+
+        *difference_pixel = Rgb(errors.map(|error| (error * 4.0 * 255.0).min(255.0) as u8));
+    }
+
+    let pixel_count: usize = (rendered.width() * rendered.height()) as usize;
+
+    Comparison {
+        rmse: (squared_error_sum / (pixel_count * 3) as f64).sqrt(),
+        different_pixels: different_pixel_count as f64 / pixel_count as f64,
+        difference,
+    }
+}
+
+fn load(path: &Path) -> RgbImage {
+    image::open(path).unwrap_or_else(|error| panic!("failed to load {}: {}", path.display(), error)).to_rgb8()
+}
+
+fn output_directory() -> PathBuf {
+    let directory: PathBuf = Path::new(env!("CARGO_TARGET_TMPDIR")).join("regression");
+    std::fs::create_dir_all(&directory).expect("failed to create the output directory");
+    directory
+}